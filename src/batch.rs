@@ -0,0 +1,144 @@
+//! all-or-nothing application of a list of changes to a FlatMap
+
+use std::fmt::Display;
+
+use crate::storage::GrowableStorage;
+use crate::{FlatMap, FlatMapEntry};
+
+/// one change in a batch for FlatMap::apply_batch
+pub enum BatchOp<K, V> {
+    /// inserts or overwrites
+    Insert(K, V),
+    /// inserts, failing with BatchError::KeyExists if the key is present
+    InsertNew(K, V),
+    /// deletes if present
+    Delete(K),
+    /// deletes, failing with BatchError::KeyMissing if the key is absent
+    DeleteExisting(K),
+}
+
+impl<K: std::fmt::Debug, V: std::fmt::Debug> std::fmt::Debug for BatchOp<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Insert(k, v) => f.debug_tuple("Insert").field(k).field(v).finish(),
+            Self::InsertNew(k, v) => f.debug_tuple("InsertNew").field(k).field(v).finish(),
+            Self::Delete(k) => f.debug_tuple("Delete").field(k).finish(),
+            Self::DeleteExisting(k) => f.debug_tuple("DeleteExisting").field(k).finish(),
+        }
+    }
+}
+
+/// why an op in a batch failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    KeyExists,
+    KeyMissing,
+    /// fixed capacity storage had no room for a new key
+    Full,
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::KeyExists => f.write_str("key already exists"),
+            Self::KeyMissing => f.write_str("key not found"),
+            Self::Full => f.write_str("storage is full"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// how to take back one applied op, undone in reverse order so indices line up again
+pub(crate) enum Undo<K, V> {
+    /// a new entry was pushed to the end
+    Pop,
+    /// the value at index was overwritten
+    Restore(usize, V),
+    /// the entry at index was swap removed
+    Reinsert(usize, FlatMapEntry<K, V>),
+}
+
+impl<K: Eq, V, S: GrowableStorage<Item = FlatMapEntry<K, V>>> FlatMap<K, V, S> {
+    /// applies ops in order, if one fails every op before it is rolled back
+    /// and the map is left exactly as it was, including entry order
+    /// on failure returns the error and the position of the failing op
+    pub fn apply_batch(
+        &mut self,
+        ops: impl IntoIterator<Item = BatchOp<K, V>>,
+    ) -> Result<(), (BatchError, usize)> {
+        self.invalidate_indices();
+
+        let mut undo = Vec::new();
+        for (position, op) in ops.into_iter().enumerate() {
+            if let Err(e) = self.apply_op(op, &mut undo) {
+                self.rollback(undo);
+                return Err((e, position));
+            }
+        }
+
+        self.apply_shrink_policy();
+        Ok(())
+    }
+
+    fn apply_op(
+        &mut self,
+        op: BatchOp<K, V>,
+        undo: &mut Vec<Undo<K, V>>,
+    ) -> Result<(), BatchError> {
+        match op {
+            BatchOp::Insert(k, v) => match self.get_index_of(&k) {
+                Some(i) => {
+                    let old = std::mem::replace(self.inner.as_mut_slice()[i].value_mut(), v);
+                    undo.push(Undo::Restore(i, old));
+                }
+                None => self.push_for_batch(FlatMapEntry::new(k, v), undo)?,
+            },
+            BatchOp::InsertNew(k, v) => match self.get_index_of(&k) {
+                Some(_) => return Err(BatchError::KeyExists),
+                None => self.push_for_batch(FlatMapEntry::new(k, v), undo)?,
+            },
+            BatchOp::Delete(k) => {
+                if let Some(i) = self.get_index_of(&k) {
+                    undo.push(Undo::Reinsert(i, self.inner.swap_remove(i)));
+                }
+            }
+            BatchOp::DeleteExisting(k) => {
+                let i = self.get_index_of(&k).ok_or(BatchError::KeyMissing)?;
+                undo.push(Undo::Reinsert(i, self.inner.swap_remove(i)));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn push_for_batch(
+        &mut self,
+        entry: FlatMapEntry<K, V>,
+        undo: &mut Vec<Undo<K, V>>,
+    ) -> Result<(), BatchError> {
+        self.grow_for_push();
+        self.inner.try_push(entry).map_err(|_| BatchError::Full)?;
+        crate::diagnostics::grew_to("FlatMap", self.len());
+        undo.push(Undo::Pop);
+        Ok(())
+    }
+
+    pub(crate) fn rollback(&mut self, undo: Vec<Undo<K, V>>) {
+        for step in undo.into_iter().rev() {
+            match step {
+                Undo::Pop => {
+                    self.inner.pop();
+                }
+                Undo::Restore(i, v) => *self.inner.as_mut_slice()[i].value_mut() = v,
+                Undo::Reinsert(i, entry) => {
+                    // the slot it was removed from is still free, so pushing can't fail
+                    if self.inner.try_push(entry).is_err() {
+                        unreachable!("FlatMap storage lost capacity during a batch");
+                    }
+                    let last = self.inner.as_slice().len() - 1;
+                    self.inner.as_mut_slice().swap(i, last);
+                }
+            }
+        }
+    }
+}
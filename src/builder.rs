@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::{DuplicateKeyError, FlatMap, FlatMapEntry};
+
+/// what FlatMapBuilder::build does when two entries have equal keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// fail with the indices of the first two entries with equal keys
+    Error,
+    /// keep the value that was pushed first
+    FirstWins,
+    /// keep the value that was pushed last
+    LastWins,
+}
+
+/// Accumulates entries without checking for duplicates, then deduplicates once on build
+pub struct FlatMapBuilder<K: Eq, V> {
+    entries: Vec<FlatMapEntry<K, V>>,
+}
+
+impl<K: Eq + Debug, V: Debug> Debug for FlatMapBuilder<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatMapBuilder")
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl<K: Eq, V> Default for FlatMapBuilder<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq, V> FlatMapBuilder<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, k: K, v: V) -> &mut Self {
+        self.entries.push(FlatMapEntry::new(k, v));
+        self
+    }
+
+    /// number of pushed entries, including duplicates
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// deduplicates according to policy, only DuplicatePolicy::Error can fail
+    /// errors carry the push order indices of the first two entries with equal keys, as find_duplicate reports them
+    /// compares every entry against each distinct key seen so far, O(n·u) for u distinct keys,
+    /// see FlatMapBuilder::build_ord and FlatMapBuilder::build_hashed for large inputs
+    pub fn build(self, policy: DuplicatePolicy) -> Result<FlatMap<K, V>, DuplicateKeyError> {
+        let first_of = self.first_of_eq();
+        self.build_with(first_of, policy)
+    }
+
+    /// build in O(n log n) by sorting push indices by key, entries stay in push order
+    pub fn build_ord(self, policy: DuplicatePolicy) -> Result<FlatMap<K, V>, DuplicateKeyError>
+    where
+        K: Ord,
+    {
+        let first_of = self.first_of_ord();
+        self.build_with(first_of, policy)
+    }
+
+    /// build in O(n) with a temporary hash map of keys, entries stay in push order
+    pub fn build_hashed(self, policy: DuplicatePolicy) -> Result<FlatMap<K, V>, DuplicateKeyError>
+    where
+        K: Hash,
+    {
+        let first_of = self.first_of_hashed();
+        self.build_with(first_of, policy)
+    }
+
+    /// deduplicates by folding every later value into the first one with merge, O(n·u) like build
+    pub fn build_merge(self, merge: impl FnMut(&K, &mut V, V)) -> FlatMap<K, V> {
+        let first_of = self.first_of_eq();
+        self.merge_with(first_of, merge)
+    }
+
+    /// build_merge in O(n log n)
+    pub fn build_merge_ord(self, merge: impl FnMut(&K, &mut V, V)) -> FlatMap<K, V>
+    where
+        K: Ord,
+    {
+        let first_of = self.first_of_ord();
+        self.merge_with(first_of, merge)
+    }
+
+    /// build_merge in O(n)
+    pub fn build_merge_hashed(self, merge: impl FnMut(&K, &mut V, V)) -> FlatMap<K, V>
+    where
+        K: Hash,
+    {
+        let first_of = self.first_of_hashed();
+        self.merge_with(first_of, merge)
+    }
+
+    fn build_with(
+        self,
+        first_of: Vec<usize>,
+        policy: DuplicatePolicy,
+    ) -> Result<FlatMap<K, V>, DuplicateKeyError> {
+        let mut error: Option<DuplicateKeyError> = None;
+        let map = self.fold(first_of, |first, _, existing, (i, v)| match policy {
+            // the first duplicate of each key is met before its later ones, so the pair with
+            // the lowest first index is the one find_duplicate would report
+            DuplicatePolicy::Error => {
+                if error.is_none_or(|e| first < e.first) {
+                    error = Some(DuplicateKeyError::new(first, i));
+                }
+            }
+            DuplicatePolicy::FirstWins => {}
+            DuplicatePolicy::LastWins => *existing = v,
+        });
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(map),
+        }
+    }
+
+    fn merge_with(
+        self,
+        first_of: Vec<usize>,
+        mut merge: impl FnMut(&K, &mut V, V),
+    ) -> FlatMap<K, V> {
+        self.fold(first_of, |_, k, existing, (_, v)| merge(k, existing, v))
+    }
+
+    /// for every pushed entry the push index of the first entry with an equal key
+    fn first_of_eq(&self) -> Vec<usize> {
+        let mut distinct: Vec<usize> = Vec::new();
+        let mut first_of = Vec::with_capacity(self.entries.len());
+        for (i, entry) in self.entries.iter().enumerate() {
+            match distinct
+                .iter()
+                .find(|&&d| self.entries[d].key() == entry.key())
+            {
+                Some(&d) => first_of.push(d),
+                None => {
+                    distinct.push(i);
+                    first_of.push(i);
+                }
+            }
+        }
+        first_of
+    }
+
+    fn first_of_ord(&self) -> Vec<usize>
+    where
+        K: Ord,
+    {
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        // stable, so each run of equal keys starts with its earliest push
+        order.sort_by(|&a, &b| self.entries[a].key().cmp(self.entries[b].key()));
+
+        let mut first_of = vec![0; self.entries.len()];
+        let mut run_start = 0;
+        for (pos, &i) in order.iter().enumerate() {
+            if pos == 0 || self.entries[order[pos - 1]].key() != self.entries[i].key() {
+                run_start = i;
+            }
+            first_of[i] = run_start;
+        }
+        first_of
+    }
+
+    fn first_of_hashed(&self) -> Vec<usize>
+    where
+        K: Hash,
+    {
+        let mut firsts: HashMap<&K, usize> = HashMap::with_capacity(self.entries.len());
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| *firsts.entry(entry.key()).or_insert(i))
+            .collect()
+    }
+
+    /// single pass over the pushed entries given the first push index of each entry's key,
+    /// on_duplicate receives the push index of the first entry with the key, the key,
+    /// its current value and the push index and value of the duplicate
+    fn fold(
+        self,
+        first_of: Vec<usize>,
+        mut on_duplicate: impl FnMut(usize, &K, &mut V, (usize, V)),
+    ) -> FlatMap<K, V> {
+        let mut inner: Vec<FlatMapEntry<K, V>> = Vec::with_capacity(self.entries.len());
+        // position in inner of the entry pushed at each index, only set for first occurrences
+        let mut slot = vec![0; self.entries.len()];
+
+        for (i, entry) in self.entries.into_iter().enumerate() {
+            let first = first_of[i];
+            if first == i {
+                slot[i] = inner.len();
+                inner.push(entry);
+            } else {
+                let (_, v) = entry.into();
+                let (k, existing) = inner[slot[first]].parts_mut();
+                on_duplicate(first, k, existing, (i, v));
+            }
+        }
+
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        FlatMap::from_inner(inner)
+    }
+}
+
+impl<K: Eq, V> Extend<(K, V)> for FlatMapBuilder<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.entries
+            .extend(iter.into_iter().map(FlatMapEntry::from));
+    }
+}
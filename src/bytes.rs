@@ -0,0 +1,127 @@
+use std::fmt::Debug;
+
+/// Map keyed by single bytes, keys are stored contiguously and searched a word at a time
+pub struct FlatByteMap<V> {
+    keys: Vec<u8>,
+    values: Vec<V>,
+}
+
+impl<V: Debug> Debug for FlatByteMap<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatByteMap")
+            .field("keys", &self.keys)
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
+impl<V: Clone> Clone for FlatByteMap<V> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            values: self.values.clone(),
+        }
+    }
+}
+
+impl<V> Default for FlatByteMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FlatByteMap<V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            keys: Vec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn get(&self, k: u8) -> Option<&V> {
+        memchr(k, &self.keys).map(|i| &self.values[i])
+    }
+
+    pub fn get_mut(&mut self, k: u8) -> Option<&mut V> {
+        memchr(k, &self.keys).map(|i| &mut self.values[i])
+    }
+
+    pub fn contains_key(&self, k: u8) -> bool {
+        memchr(k, &self.keys).is_some()
+    }
+
+    pub fn insert(&mut self, k: u8, v: V) -> Option<V> {
+        match memchr(k, &self.keys) {
+            Some(i) => Some(std::mem::replace(&mut self.values[i], v)),
+            None => {
+                self.keys.push(k);
+                self.values.push(v);
+                None
+            }
+        }
+    }
+
+    pub fn delete(&mut self, k: u8) -> Option<V> {
+        let i = memchr(k, &self.keys)?;
+        self.keys.swap_remove(i);
+        Some(self.values.swap_remove(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> &[u8] {
+        &self.keys
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &V)> {
+        self.keys.iter().copied().zip(self.values.iter())
+    }
+}
+
+impl<V> FromIterator<(u8, V)> for FlatByteMap<V> {
+    /// last duplicate stays
+    fn from_iter<T: IntoIterator<Item = (u8, V)>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+        let mut map = Self::with_capacity(iter.size_hint().0);
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+/// index of the first needle in haystack, compares eight bytes per step
+pub(crate) fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    const LO: u64 = 0x0101_0101_0101_0101;
+    const HI: u64 = 0x8080_8080_8080_8080;
+
+    let repeated = LO * u64::from(needle);
+    let mut chunks = haystack.chunks_exact(8);
+    let mut offset = 0;
+
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap()) ^ repeated;
+        // high bit set in every byte of word that is zero, i.e. matched the needle
+        let found = word.wrapping_sub(LO) & !word & HI;
+        if found != 0 {
+            return Some(offset + (found.trailing_zeros() / 8) as usize);
+        }
+        offset += 8;
+    }
+
+    chunks
+        .remainder()
+        .iter()
+        .position(|&b| b == needle)
+        .map(|i| offset + i)
+}
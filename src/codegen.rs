@@ -0,0 +1,119 @@
+//! helpers for build scripts that emit Rust source declaring validated static tables
+//!
+//! ```no_run
+//! // build.rs
+//! let csv = std::fs::File::open("data/countries.csv").unwrap();
+//! let mut table = flatmap::codegen::ConstantTable::new("COUNTRIES", "&'static str", "&'static str");
+//! table.read_csv(std::io::BufReader::new(csv)).unwrap();
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("countries.rs");
+//! std::fs::write(out, table.generate().unwrap()).unwrap();
+//! ```
+
+use std::fmt::Write as _;
+use std::io::{self, BufRead};
+
+use crate::DuplicateKeyError;
+use crate::csv::read_csv_rows;
+
+/// Static ConstantFlatMap declaration built up from Rust expressions for keys and values
+pub struct ConstantTable {
+    name: String,
+    key_type: String,
+    value_type: String,
+    entries: Vec<(String, String)>,
+    // every key went through str_entry, so equal keys have equal literals
+    literal_keys: bool,
+}
+
+impl ConstantTable {
+    /// key_type and value_type are inserted verbatim into the generated declaration
+    pub fn new(name: &str, key_type: &str, value_type: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            key_type: key_type.to_owned(),
+            value_type: value_type.to_owned(),
+            entries: Vec::new(),
+            literal_keys: true,
+        }
+    }
+
+    /// adds an entry from Rust expressions, e.g. `("1u32", "Some('a')")`
+    /// different expressions may evaluate to equal keys, so a table with any of these
+    /// is checked when it is first used instead of when it is generated
+    pub fn entry(&mut self, key_expr: &str, value_expr: &str) -> &mut Self {
+        self.entries
+            .push((key_expr.to_owned(), value_expr.to_owned()));
+        self.literal_keys = false;
+        self
+    }
+
+    /// adds an entry whose key and value are emitted as string literals
+    pub fn str_entry(&mut self, key: &str, value: &str) -> &mut Self {
+        self.entries
+            .push((format!("{key:?}"), format!("{value:?}")));
+        self
+    }
+
+    /// reads `key,value` rows as string entries, fields may be wrapped in double quotes
+    /// empty lines and lines starting with `#` are skipped
+    pub fn read_csv(&mut self, reader: impl BufRead) -> io::Result<&mut Self> {
+        read_csv_rows(reader, |key, value| {
+            self.str_entry(&key, &value);
+        })?;
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// emits the declaration, fails with the indices of the first two identical key expressions
+    /// a table of only str_entry keys is fully checked here and emitted as a plain static,
+    /// otherwise it is emitted as a LazyLock that checks the evaluated keys on first use and panics on duplicates
+    pub fn generate(&self) -> Result<String, DuplicateKeyError> {
+        for i in 0..self.entries.len() {
+            for j in (i + 1)..self.entries.len() {
+                if self.entries[i].0 == self.entries[j].0 {
+                    return Err(DuplicateKeyError::new(i, j));
+                }
+            }
+        }
+
+        let map_type = format!(
+            "::flatmap::ConstantFlatMap<{}, {}, {}>",
+            self.key_type,
+            self.value_type,
+            self.entries.len()
+        );
+        let mut out = String::new();
+        if self.literal_keys {
+            let _ = writeln!(out, "pub static {}: {} = unsafe {{", self.name, map_type);
+            out.push_str("    ::flatmap::ConstantFlatMap::from_entries_unchecked([\n");
+        } else {
+            let _ = writeln!(
+                out,
+                "pub static {}: ::std::sync::LazyLock<{}> = ::std::sync::LazyLock::new(|| {{",
+                self.name, map_type
+            );
+            out.push_str("    ::flatmap::ConstantFlatMap::from_entries([\n");
+        }
+        for (key, value) in &self.entries {
+            let _ = writeln!(out, "        ::flatmap::FlatMapEntry::new({key}, {value}),");
+        }
+        if self.literal_keys {
+            out.push_str("    ])\n};\n");
+        } else {
+            let _ = writeln!(
+                out,
+                "    ])\n    .expect(\"duplicate keys in {}\")\n}});",
+                self.name
+            );
+        }
+
+        Ok(out)
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt::Debug;
+
+/// key span into the shared buffer with its value
+struct CompactEntry<V> {
+    start: u32,
+    len: u32,
+    value: V,
+}
+
+impl<V: Clone> Clone for CompactEntry<V> {
+    fn clone(&self) -> Self {
+        Self {
+            start: self.start,
+            len: self.len,
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// Map keyed by strings whose bytes all live in one buffer, so keys don't allocate one by one
+/// deleted keys leave their bytes behind until more than half of the buffer is unused
+pub struct CompactStrFlatMap<V> {
+    keys: String,
+    entries: Vec<CompactEntry<V>>,
+    // bytes in keys no entry points at anymore
+    dead: usize,
+}
+
+impl<V: Debug> Debug for CompactStrFlatMap<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V: Clone> Clone for CompactStrFlatMap<V> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            entries: self.entries.clone(),
+            dead: self.dead,
+        }
+    }
+}
+
+impl<V> Default for CompactStrFlatMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> CompactStrFlatMap<V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0, 0)
+    }
+
+    /// room for capacity entries whose keys add up to key_bytes
+    pub fn with_capacity(capacity: usize, key_bytes: usize) -> Self {
+        Self {
+            keys: String::with_capacity(key_bytes),
+            entries: Vec::with_capacity(capacity),
+            dead: 0,
+        }
+    }
+
+    fn key_of(&self, entry: &CompactEntry<V>) -> &str {
+        let start = entry.start as usize;
+        &self.keys[start..start + entry.len as usize]
+    }
+
+    fn find(&self, k: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.len as usize == k.len() && self.key_of(entry) == k)
+    }
+
+    pub fn get(&self, k: &str) -> Option<&V> {
+        self.find(k).map(|i| &self.entries[i].value)
+    }
+
+    pub fn get_mut(&mut self, k: &str) -> Option<&mut V> {
+        self.find(k).map(|i| &mut self.entries[i].value)
+    }
+
+    pub fn contains_key(&self, k: &str) -> bool {
+        self.find(k).is_some()
+    }
+
+    /// copies k into the key buffer if it is new, returns the previous value otherwise
+    /// panics if the key buffer would grow past u32::MAX bytes
+    pub fn insert(&mut self, k: &str, v: V) -> Option<V> {
+        if let Some(i) = self.find(k) {
+            return Some(std::mem::replace(&mut self.entries[i].value, v));
+        }
+
+        assert!(
+            self.keys.len() + k.len() <= u32::MAX as usize,
+            "CompactStrFlatMap key buffer holds at most u32::MAX bytes"
+        );
+        let start = self.keys.len() as u32;
+        self.keys.push_str(k);
+        self.entries.push(CompactEntry {
+            start,
+            len: k.len() as u32,
+            value: v,
+        });
+        None
+    }
+
+    pub fn delete(&mut self, k: &str) -> Option<V> {
+        let i = self.find(k)?;
+        let entry = self.entries.swap_remove(i);
+        self.dead += entry.len as usize;
+        if self.dead * 2 > self.keys.len() {
+            self.compact();
+        }
+        Some(entry.value)
+    }
+
+    /// rewrites the key buffer without the bytes of deleted keys
+    pub fn compact(&mut self) {
+        let mut keys = String::with_capacity(self.keys.len() - self.dead);
+        for entry in &mut self.entries {
+            let start = entry.start as usize;
+            let key = &self.keys[start..start + entry.len as usize];
+            entry.start = keys.len() as u32;
+            keys.push_str(key);
+        }
+        self.keys = keys;
+        self.dead = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// bytes in the key buffer, including those of deleted keys not yet compacted
+    pub fn key_bytes(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|entry| self.key_of(entry))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.entries
+            .iter()
+            .map(|entry| (self.key_of(entry), &entry.value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut V)> {
+        let keys = &self.keys;
+        self.entries.iter_mut().map(move |entry| {
+            let start = entry.start as usize;
+            (&keys[start..start + entry.len as usize], &mut entry.value)
+        })
+    }
+}
+
+impl<'a, V> FromIterator<(&'a str, V)> for CompactStrFlatMap<V> {
+    /// last duplicate stays
+    fn from_iter<T: IntoIterator<Item = (&'a str, V)>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+        let mut map = Self::with_capacity(iter.size_hint().0, 0);
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
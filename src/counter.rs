@@ -0,0 +1,135 @@
+use std::fmt::Debug;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// Linear Map from keys to how often they were counted
+pub struct FlatCounter<K: Eq> {
+    inner: FlatMap<K, u64>,
+}
+
+impl<K: Eq + Debug> Debug for FlatCounter<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatCounter")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq + Clone> Clone for FlatCounter<K> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Eq> Default for FlatCounter<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq> FlatCounter<K> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: FlatMap::with_capacity(capacity),
+        }
+    }
+
+    /// counts every key in iter
+    pub fn from_keys(iter: impl IntoIterator<Item = K>) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+
+    /// how often k was counted, 0 if never
+    pub fn get(&self, k: &K) -> u64 {
+        self.inner.get(k).copied().unwrap_or(0)
+    }
+
+    /// adds n to the count of k and returns the new count, saturating at u64::MAX
+    pub fn add_owned(&mut self, k: K, n: u64) -> u64 {
+        let count = self.inner.get_or_insert_with(k, || 0);
+        *count = count.saturating_add(n);
+        *count
+    }
+
+    /// number of distinct keys
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// sum of all counts, saturating at u64::MAX
+    pub fn total(&self) -> u64 {
+        self.inner
+            .iter()
+            .fold(0u64, |sum, entry| sum.saturating_add(*entry.value()))
+    }
+
+    pub fn delete(&mut self, k: &K) -> Option<u64> {
+        self.inner.delete(k)
+    }
+
+    /// entries in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, u64>> {
+        self.inner.iter()
+    }
+
+    /// entries from the highest count to the lowest, equal counts keep insertion order
+    pub fn iter_by_count(&self) -> impl Iterator<Item = &FlatMapEntry<K, u64>> {
+        let mut entries: Vec<_> = self.inner.iter().collect();
+        entries.sort_by(|a, b| b.value().cmp(a.value()));
+        entries.into_iter()
+    }
+
+    /// the n entries with the highest counts, highest first
+    pub fn most_common(&self, n: usize) -> Vec<&FlatMapEntry<K, u64>> {
+        self.iter_by_count().take(n).collect()
+    }
+
+    pub fn into_map(self) -> FlatMap<K, u64> {
+        self.inner
+    }
+}
+
+impl<K: Eq + Clone> FlatCounter<K> {
+    /// adds one to the count of k and returns the new count
+    pub fn increment(&mut self, k: &K) -> u64 {
+        self.add(k, 1)
+    }
+
+    /// adds n to the count of k and returns the new count, saturating at u64::MAX
+    /// k is only cloned when it is new
+    pub fn add(&mut self, k: &K, n: u64) -> u64 {
+        if let Some(i) = self.inner.get_index_of(k) {
+            let (_, count) = self.inner.get_index_mut(i).unwrap();
+            *count = count.saturating_add(n);
+            return *count;
+        }
+        self.inner.insert(k.clone(), n);
+        n
+    }
+}
+
+impl<K: Eq> Extend<K> for FlatCounter<K> {
+    fn extend<T: IntoIterator<Item = K>>(&mut self, iter: T) {
+        for k in iter {
+            self.add_owned(k, 1);
+        }
+    }
+}
+
+impl<K: Eq> From<FlatMap<K, u64>> for FlatCounter<K> {
+    fn from(inner: FlatMap<K, u64>) -> Self {
+        Self { inner }
+    }
+}
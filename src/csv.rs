@@ -0,0 +1,112 @@
+//! minimal `key,value` CSV reading shared by the codegen and csv features
+
+use std::io::{self, BufRead};
+
+/// calls f with the key and value of every row, fields may be wrapped in double quotes
+/// whitespace around fields is trimmed, whitespace inside quotes is kept
+/// empty lines and lines starting with `#` are skipped
+/// fails with InvalidData on a row that isn't two fields or has a quote that is never closed
+pub(crate) fn read_csv_rows(
+    reader: impl BufRead,
+    mut f: impl FnMut(String, String),
+) -> io::Result<()> {
+    for (row, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = split_csv_row(line).map_err(|reason| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("row {} {reason}", row + 1),
+            )
+        })?;
+        f(key, value);
+    }
+
+    Ok(())
+}
+
+/// field text with the whitespace around it trimmed, but never the whitespace inside quotes
+struct Field {
+    text: String,
+    // span of text between the first opening and the last closing quote
+    quoted: Option<(usize, usize)>,
+}
+
+impl Field {
+    fn new() -> Self {
+        Self {
+            text: String::new(),
+            quoted: None,
+        }
+    }
+
+    fn finish(self) -> String {
+        match self.quoted {
+            None => self.text.trim().to_owned(),
+            Some((start, end)) => {
+                let mut out = self.text[..start].trim_start().to_owned();
+                out.push_str(&self.text[start..end]);
+                out.push_str(self.text[end..].trim_end());
+                out
+            }
+        }
+    }
+}
+
+/// key and value of a row, or why it isn't one
+fn split_csv_row(line: &str) -> Result<(String, String), &'static str> {
+    let mut fields = Vec::with_capacity(2);
+    let mut field = Field::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.text.push('"');
+            }
+            '"' => {
+                let at = field.text.len();
+                match (quoted, &mut field.quoted) {
+                    (false, None) => field.quoted = Some((at, at)),
+                    (true, Some((_, end))) => *end = at,
+                    _ => {}
+                }
+                quoted = !quoted;
+            }
+            ',' if !quoted => fields.push(std::mem::replace(&mut field, Field::new()).finish()),
+            c => field.text.push(c),
+        }
+    }
+    if quoted {
+        return Err("has an unterminated quote");
+    }
+    fields.push(field.finish());
+
+    match <[String; 2]>::try_from(fields) {
+        Ok([key, value]) => Ok((key, value)),
+        Err(_) => Err("is not a key,value pair"),
+    }
+}
+
+#[cfg(feature = "csv")]
+impl crate::FlatMap<String, String> {
+    /// reads `key,value` rows, fields may be wrapped in double quotes to keep surrounding whitespace
+    /// empty lines and lines starting with `#` are skipped, a quote left open fails with InvalidData
+    /// repeated keys are resolved by policy, DuplicatePolicy::Error fails with InvalidData
+    /// wrapping the DuplicateKeyError, its indices count rows that hold entries
+    pub fn from_csv(reader: impl BufRead, policy: crate::DuplicatePolicy) -> io::Result<Self> {
+        let mut builder = crate::FlatMapBuilder::new();
+        read_csv_rows(reader, |key, value| {
+            builder.push(key, value);
+        })?;
+        builder
+            .build_hashed(policy)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
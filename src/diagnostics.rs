@@ -0,0 +1,48 @@
+//! one-time warnings when a flat collection grows past the size where hashing is almost certainly faster
+//! enabled with the `size-diagnostics` feature, otherwise every check compiles to nothing
+
+#[cfg(feature = "size-diagnostics")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+pub const DEFAULT_SIZE_WARNING_THRESHOLD: usize = 256;
+
+#[cfg(feature = "size-diagnostics")]
+static SIZE_WARNING_THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_SIZE_WARNING_THRESHOLD);
+
+#[cfg(feature = "size-diagnostics")]
+static SIZE_WARNING_EMITTED: AtomicBool = AtomicBool::new(false);
+
+/// sets the length past which growing a FlatMap or FlatSet prints a warning to stderr
+#[cfg(feature = "size-diagnostics")]
+pub fn set_size_warning_threshold(len: usize) {
+    SIZE_WARNING_THRESHOLD.store(len, Ordering::Relaxed);
+}
+
+#[cfg(feature = "size-diagnostics")]
+pub fn size_warning_threshold() -> usize {
+    SIZE_WARNING_THRESHOLD.load(Ordering::Relaxed)
+}
+
+/// whether the warning was printed, it is printed at most once per process
+#[cfg(feature = "size-diagnostics")]
+pub fn size_warning_emitted() -> bool {
+    SIZE_WARNING_EMITTED.load(Ordering::Relaxed)
+}
+
+/// called after a collection grew to len, by one or in bulk,
+/// warns the first time any collection is past the threshold
+#[inline(always)]
+#[cfg_attr(not(feature = "size-diagnostics"), allow(unused_variables))]
+pub(crate) fn grew_to(type_name: &str, len: usize) {
+    #[cfg(feature = "size-diagnostics")]
+    if len > size_warning_threshold()
+        && !SIZE_WARNING_EMITTED.load(Ordering::Relaxed)
+        && !SIZE_WARNING_EMITTED.swap(true, Ordering::Relaxed)
+    {
+        eprintln!(
+            "flatmap: {} grew beyond {} entries, consider a hash based collection",
+            type_name,
+            size_warning_threshold()
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+/// two entries with equal keys, first < second are their indices
+/// converts into the (usize, usize) pair earlier versions returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateKeyError {
+    pub first: usize,
+    pub second: usize,
+}
+
+impl DuplicateKeyError {
+    pub const fn new(first: usize, second: usize) -> Self {
+        Self { first, second }
+    }
+}
+
+impl Display for DuplicateKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "duplicate keys at indices {} and {}",
+            self.first, self.second
+        )
+    }
+}
+
+impl std::error::Error for DuplicateKeyError {}
+
+impl From<DuplicateKeyError> for (usize, usize) {
+    fn from(e: DuplicateKeyError) -> Self {
+        (e.first, e.second)
+    }
+}
+
+/// indices of the first pair of items considered equal by eq, O(n²)
+pub(crate) fn find_duplicate<T>(
+    items: &[T],
+    eq: impl Fn(&T, &T) -> bool,
+) -> Option<DuplicateKeyError> {
+    for i in 0..items.len() {
+        for j in (i + 1)..items.len() {
+            if eq(&items[i], &items[j]) {
+                return Some(DuplicateKeyError::new(i, j));
+            }
+        }
+    }
+
+    None
+}
+
+/// indices of every group of items considered equal by eq, groups ordered by their first index, O(n²)
+pub(crate) fn find_duplicate_groups<T>(
+    items: &[T],
+    eq: impl Fn(&T, &T) -> bool,
+) -> Vec<Vec<usize>> {
+    let mut grouped = vec![false; items.len()];
+    let mut groups = Vec::new();
+    for i in 0..items.len() {
+        if grouped[i] {
+            continue;
+        }
+        let mut group = vec![i];
+        for j in (i + 1)..items.len() {
+            if !grouped[j] && eq(&items[i], &items[j]) {
+                grouped[j] = true;
+                group.push(j);
+            }
+        }
+        if group.len() > 1 {
+            groups.push(group);
+        }
+    }
+
+    groups
+}
+
+/// same result as find_duplicate but O(n log n), sorts a scratch buffer of indices by key
+pub(crate) fn find_duplicate_ord<T, K: Ord>(
+    items: &[T],
+    key: impl Fn(&T) -> &K,
+) -> Option<DuplicateKeyError> {
+    let mut indices: Vec<usize> = (0..items.len()).collect();
+    // stable, so equal keys stay in index order
+    indices.sort_by(|&a, &b| key(&items[a]).cmp(key(&items[b])));
+
+    let mut first: Option<DuplicateKeyError> = None;
+    for pair in indices.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if key(&items[a]) == key(&items[b]) && first.is_none_or(|e| a < e.first) {
+            first = Some(DuplicateKeyError::new(a, b));
+        }
+    }
+
+    first
+}
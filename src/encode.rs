@@ -0,0 +1,264 @@
+//! compact length-prefixed binary encoding for flat collections without serde
+//! integers are little endian, lengths are LEB128 varints
+
+use std::cmp::Ordering;
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use crate::duplicates::{DuplicateKeyError, find_duplicate};
+use crate::{FlatMap, FlatMapEntry, FlatSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// input ended in the middle of a value
+    UnexpectedEnd,
+    /// bytes don't form a valid value, e.g. invalid utf-8 or bool
+    InvalidData,
+    /// decoded collection has equal keys
+    Duplicate(DuplicateKeyError),
+    /// input has bytes left after the collection
+    TrailingBytes,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEnd => f.write_str("unexpected end of input"),
+            Self::InvalidData => f.write_str("invalid data"),
+            Self::Duplicate(e) => e.fmt(f),
+            Self::TrailingBytes => f.write_str("trailing bytes after collection"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+pub trait Encode {
+    fn encode(&self, out: &mut impl Write) -> io::Result<()>;
+}
+
+pub trait Decode: Sized {
+    /// decodes a value from the front of input and advances it
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError>;
+}
+
+fn take<'a>(input: &mut &'a [u8], n: usize) -> Result<&'a [u8], DecodeError> {
+    if input.len() < n {
+        return Err(DecodeError::UnexpectedEnd);
+    }
+    let (head, rest) = input.split_at(n);
+    *input = rest;
+    Ok(head)
+}
+
+fn encode_len(len: usize, out: &mut impl Write) -> io::Result<()> {
+    let mut len = len as u64;
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            return out.write_all(&[byte]);
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn decode_len(input: &mut &[u8]) -> Result<usize, DecodeError> {
+    let mut len = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(input, 1)?[0];
+        len |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return usize::try_from(len).map_err(|_| DecodeError::InvalidData);
+        }
+    }
+
+    Err(DecodeError::InvalidData)
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {
+        $(
+            impl Encode for $t {
+                fn encode(&self, out: &mut impl Write) -> io::Result<()> {
+                    out.write_all(&self.to_le_bytes())
+                }
+            }
+
+            impl Decode for $t {
+                fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+                    let bytes = take(input, std::mem::size_of::<$t>())?;
+                    Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl Encode for bool {
+    fn encode(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(&[u8::from(*self)])
+    }
+}
+
+impl Decode for bool {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        match take(input, 1)?[0] {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::InvalidData),
+        }
+    }
+}
+
+impl Encode for str {
+    fn encode(&self, out: &mut impl Write) -> io::Result<()> {
+        encode_len(self.len(), out)?;
+        out.write_all(self.as_bytes())
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut impl Write) -> io::Result<()> {
+        self.as_str().encode(out)
+    }
+}
+
+impl Decode for String {
+    fn decode(input: &mut &[u8]) -> Result<Self, DecodeError> {
+        let len = decode_len(input)?;
+        let bytes = take(input, len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| DecodeError::InvalidData)
+    }
+}
+
+impl<K: Eq + Encode, V: Encode> FlatMap<K, V> {
+    /// writes the entry count followed by every key and value
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        encode_len(self.len(), out)?;
+        for entry in self.iter() {
+            entry.key().encode(out)?;
+            entry.value().encode(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Eq + Decode, V: Decode> FlatMap<K, V> {
+    /// reads a map written by FlatMap::write_to, rejects duplicate keys and trailing bytes
+    pub fn read_from(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        let len = decode_len(&mut bytes)?;
+        // every entry takes at least one byte, so don't trust len beyond the input size
+        let mut inner = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            let k = K::decode(&mut bytes)?;
+            let v = V::decode(&mut bytes)?;
+            inner.push(FlatMapEntry::new(k, v));
+        }
+
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        if let Some(e) = find_duplicate(&inner, |a, b| a.key() == b.key()) {
+            return Err(DecodeError::Duplicate(e));
+        }
+
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        Ok(Self::from_inner(inner))
+    }
+}
+
+impl<K: Eq + Encode> FlatSet<K> {
+    /// writes the item count followed by every item
+    pub fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        encode_len(self.as_slice().len(), out)?;
+        for item in self.iter() {
+            item.encode(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Eq + Decode> FlatSet<K> {
+    /// reads a set written by FlatSet::write_to, rejects duplicate items and trailing bytes
+    pub fn read_from(mut bytes: &[u8]) -> Result<Self, DecodeError> {
+        let len = decode_len(&mut bytes)?;
+        let mut inner = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
+            inner.push(K::decode(&mut bytes)?);
+        }
+
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        if let Some(e) = find_duplicate(&inner, |a, b| a == b) {
+            return Err(DecodeError::Duplicate(e));
+        }
+
+        crate::diagnostics::grew_to("FlatSet", inner.len());
+        Ok(Self::from_inner(inner))
+    }
+}
+
+/// start of every blob written by FlatMap::write_blob, the last byte is the format version
+pub const BLOB_MAGIC: &[u8; 5] = b"FLAT\x01";
+
+impl<K: Ord + Encode, V: Encode> FlatMap<K, V> {
+    /// writes a blob for include_flatmap!, entries go in ascending key order
+    /// so loading can verify there are no duplicates in O(n)
+    pub fn write_blob(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(BLOB_MAGIC)?;
+        encode_len(self.len(), out)?;
+        for entry in self.iter_sorted() {
+            entry.key().encode(out)?;
+            entry.value().encode(out)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord + Decode, V: Decode> FlatMap<K, V> {
+    /// reads a blob written by FlatMap::write_blob, the result is in ascending key order
+    /// a missing header or keys out of order are InvalidData, equal neighbouring keys Duplicate
+    pub fn read_blob(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut bytes = bytes
+            .strip_prefix(BLOB_MAGIC.as_slice())
+            .ok_or(DecodeError::InvalidData)?;
+        let len = decode_len(&mut bytes)?;
+        let mut inner: Vec<FlatMapEntry<K, V>> = Vec::with_capacity(len.min(bytes.len()));
+        for i in 0..len {
+            let k = K::decode(&mut bytes)?;
+            let v = V::decode(&mut bytes)?;
+            match inner.last().map(|last| last.key().cmp(&k)) {
+                Some(Ordering::Equal) => {
+                    return Err(DecodeError::Duplicate(DuplicateKeyError::new(i - 1, i)));
+                }
+                Some(Ordering::Greater) => return Err(DecodeError::InvalidData),
+                _ => inner.push(FlatMapEntry::new(k, v)),
+            }
+        }
+
+        if !bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        Ok(Self::from_inner(inner))
+    }
+}
+
+/// loads a blob written by FlatMap::write_blob and embedded with include_bytes!
+/// evaluates to Result<FlatMap<K, V>, DecodeError>, the path is relative to the calling file
+/// ```ignore
+/// static COUNTRIES: LazyLock<FlatMap<String, String>> = LazyLock::new(|| {
+///     flatmap::include_flatmap!(String, String, concat!(env!("OUT_DIR"), "/countries.bin")).unwrap()
+/// });
+/// ```
+#[macro_export]
+macro_rules! include_flatmap {
+    ($k:ty, $v:ty, $path:expr $(,)?) => {
+        $crate::FlatMap::<$k, $v>::read_blob(include_bytes!($path))
+    };
+}
@@ -0,0 +1,113 @@
+use std::borrow::Borrow;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// Entry looked up by a borrowed key, the key is only converted into an owned K on insert
+/// created with FlatMap::entry_ref
+pub enum EntryRef<'a, 'b, K: Eq, V, Q: ?Sized> {
+    Occupied(OccupiedEntryRef<'a, K, V>),
+    Vacant(VacantEntryRef<'a, 'b, K, V, Q>),
+}
+
+pub struct OccupiedEntryRef<'a, K, V> {
+    entry: &'a mut FlatMapEntry<K, V>,
+}
+
+pub struct VacantEntryRef<'a, 'b, K: Eq, V, Q: ?Sized> {
+    map: &'a mut FlatMap<K, V>,
+    key: &'b Q,
+}
+
+impl<K: Eq, V> FlatMap<K, V> {
+    /// looks up a borrowed key, only allocates an owned key when a vacant entry gets inserted
+    pub fn entry_ref<'a, 'b, Q>(&'a mut self, key: &'b Q) -> EntryRef<'a, 'b, K, V, Q>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        match self
+            .inner
+            .iter()
+            .position(|entry| entry.key().borrow() == key)
+        {
+            Some(i) => EntryRef::Occupied(OccupiedEntryRef {
+                entry: &mut self.inner[i],
+            }),
+            None => EntryRef::Vacant(VacantEntryRef { map: self, key }),
+        }
+    }
+}
+
+impl<'a, 'b, K: Eq, V, Q: ?Sized> EntryRef<'a, 'b, K, V, Q> {
+    pub fn or_insert(self, default: V) -> &'a mut V
+    where
+        K: From<&'b Q>,
+    {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'a mut V
+    where
+        K: From<&'b Q>,
+    {
+        match self {
+            Self::Occupied(entry) => entry.into_mut(),
+            Self::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        K: From<&'b Q>,
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    /// applies f to the value if the entry is occupied
+    pub fn and_modify(mut self, f: impl FnOnce(&mut V)) -> Self {
+        if let Self::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntryRef<'a, K, V> {
+    pub fn key(&self) -> &K {
+        self.entry.key()
+    }
+
+    pub fn get(&self) -> &V {
+        self.entry.value()
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.entry.value_mut()
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.entry.value_mut()
+    }
+
+    /// replaces the value, returning the old one
+    pub fn insert(&mut self, value: V) -> V {
+        std::mem::replace(self.entry.value_mut(), value)
+    }
+}
+
+impl<'a, 'b, K: Eq, V, Q: ?Sized> VacantEntryRef<'a, 'b, K, V, Q> {
+    pub fn key(&self) -> &'b Q {
+        self.key
+    }
+
+    /// converts the borrowed key into K and inserts the value without rescanning
+    pub fn insert(self, value: V) -> &'a mut V
+    where
+        K: From<&'b Q>,
+    {
+        self.map
+            .push_entry(FlatMapEntry::new(K::from(self.key), value));
+        self.map.inner.last_mut().unwrap().value_mut()
+    }
+}
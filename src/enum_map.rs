@@ -0,0 +1,103 @@
+use std::fmt::Debug;
+
+use crate::{ConstantFlatMap, FlatMapEntry};
+
+/// Fieldless enum usable as an exhaustive key, implement with the flatmap_key! macro
+pub trait FlatMapKey: Copy + Eq + 'static {
+    /// every variant exactly once
+    const VARIANTS: &'static [Self];
+    const COUNT: usize = Self::VARIANTS.len();
+}
+
+/// declares a fieldless enum and implements FlatMapKey for it
+/// ```
+/// flatmap::flatmap_key! {
+///     #[derive(Debug)]
+///     pub enum Color { Red, Green, Blue }
+/// }
+///
+/// let table: flatmap::EnumFlatMap<Color, u8, { <Color as flatmap::FlatMapKey>::COUNT }> =
+///     flatmap::EnumFlatMap::from_fn(|color| match color {
+///         Color::Red => 1,
+///         Color::Green => 2,
+///         Color::Blue => 3,
+///     });
+/// assert_eq!(table.get(&Color::Green), &2);
+/// ```
+#[macro_export]
+macro_rules! flatmap_key {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant),*
+        }
+
+        impl $crate::FlatMapKey for $name {
+            const VARIANTS: &'static [Self] = &[$($name::$variant),*];
+        }
+    };
+}
+
+/// ConstantFlatMap holding exactly one value for every variant of an enum key
+/// N must equal K::COUNT, checked at compile time
+pub struct EnumFlatMap<K: FlatMapKey, V, const N: usize> {
+    inner: ConstantFlatMap<K, V, N>,
+}
+
+impl<K: FlatMapKey + Debug, V: Debug, const N: usize> Debug for EnumFlatMap<K, V, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnumFlatMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: FlatMapKey, V: Clone, const N: usize> Clone for EnumFlatMap<K, V, N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: FlatMapKey, V, const N: usize> EnumFlatMap<K, V, N> {
+    /// builds the table by asking f for the value of every variant
+    /// matching exhaustively inside f turns a missing variant into a compile error
+    pub fn from_fn(mut f: impl FnMut(K) -> V) -> Self {
+        const { assert!(N == K::COUNT, "N must equal the number of enum variants") };
+
+        let entries = std::array::from_fn(|i| FlatMapEntry::new(K::VARIANTS[i], f(K::VARIANTS[i])));
+        Self {
+            inner: unsafe { ConstantFlatMap::from_entries_unchecked(entries) },
+        }
+    }
+
+    /// every variant has a value, so lookups can't fail
+    pub fn get(&self, key: &K) -> &V {
+        self.inner
+            .get(key)
+            .expect("EnumFlatMap holds every variant")
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> &mut V {
+        self.inner
+            .iter_mut()
+            .find(|entry| entry.key() == key)
+            .expect("EnumFlatMap holds every variant")
+            .value_mut()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.inner.iter()
+    }
+
+    pub fn into_inner(self) -> ConstantFlatMap<K, V, N> {
+        self.inner
+    }
+}
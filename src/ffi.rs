@@ -0,0 +1,106 @@
+//! C ABI for a FlatMap<u64, u64>, enabled with the `ffi` feature
+//!
+//! ```c
+//! typedef struct FlatMapU64 FlatMapU64;
+//! typedef struct { uint64_t key; uint64_t value; } FlatMapEntryU64;
+//!
+//! FlatMapU64 *flatmap_u64_new(void);
+//! void flatmap_u64_free(FlatMapU64 *map);
+//! bool flatmap_u64_insert(FlatMapU64 *map, uint64_t key, uint64_t value, uint64_t *old);
+//! bool flatmap_u64_get(const FlatMapU64 *map, uint64_t key, uint64_t *value);
+//! bool flatmap_u64_delete(FlatMapU64 *map, uint64_t key, uint64_t *old);
+//! size_t flatmap_u64_len(const FlatMapU64 *map);
+//! const FlatMapEntryU64 *flatmap_u64_entries(const FlatMapU64 *map, size_t *len);
+//! ```
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// opaque handle as seen from C
+pub type FlatMapU64 = FlatMap<u64, u64>;
+
+/// allocates an empty map, release it with flatmap_u64_free
+#[unsafe(no_mangle)]
+pub extern "C" fn flatmap_u64_new() -> *mut FlatMapU64 {
+    Box::into_raw(Box::new(FlatMap::new()))
+}
+
+/// # Safety
+/// map must come from flatmap_u64_new and not be used afterwards, null is ignored
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flatmap_u64_free(map: *mut FlatMapU64) {
+    if !map.is_null() {
+        drop(unsafe { Box::from_raw(map) });
+    }
+}
+
+/// returns true and writes the replaced value to old (if not null) when key already existed
+///
+/// # Safety
+/// map must be a valid handle, old must be null or valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flatmap_u64_insert(
+    map: *mut FlatMapU64,
+    key: u64,
+    value: u64,
+    old: *mut u64,
+) -> bool {
+    let map = unsafe { &mut *map };
+    unsafe { write_out(map.insert(key, value), old) }
+}
+
+/// returns true and writes the value to value (if not null) when key exists
+///
+/// # Safety
+/// map must be a valid handle, value must be null or valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flatmap_u64_get(
+    map: *const FlatMapU64,
+    key: u64,
+    value: *mut u64,
+) -> bool {
+    let map = unsafe { &*map };
+    unsafe { write_out(map.get(&key).copied(), value) }
+}
+
+/// returns true and writes the removed value to old (if not null) when key existed
+///
+/// # Safety
+/// map must be a valid handle, old must be null or valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flatmap_u64_delete(map: *mut FlatMapU64, key: u64, old: *mut u64) -> bool {
+    let map = unsafe { &mut *map };
+    unsafe { write_out(map.delete(&key), old) }
+}
+
+/// # Safety
+/// map must be a valid handle
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flatmap_u64_len(map: *const FlatMapU64) -> usize {
+    unsafe { &*map }.len()
+}
+
+/// raw view of the entries, valid until the map is next modified or freed
+///
+/// # Safety
+/// map must be a valid handle, len must be valid for writes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn flatmap_u64_entries(
+    map: *const FlatMapU64,
+    len: *mut usize,
+) -> *const FlatMapEntry<u64, u64> {
+    let entries = unsafe { &*map }.as_slice();
+    unsafe { len.write(entries.len()) };
+    entries.as_ptr()
+}
+
+unsafe fn write_out(value: Option<u64>, out: *mut u64) -> bool {
+    match value {
+        Some(value) => {
+            if !out.is_null() {
+                unsafe { out.write(value) };
+            }
+            true
+        }
+        None => false,
+    }
+}
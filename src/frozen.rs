@@ -0,0 +1,62 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::map::find_index;
+use crate::{FlatMap, FlatMapEntry};
+
+/// Immutable map with no spare capacity, cloning only bumps a reference count
+/// created with FlatMap::freeze
+pub struct FrozenFlatMap<K: Eq, V> {
+    inner: Arc<[FlatMapEntry<K, V>]>,
+}
+
+impl<K: Eq + Debug, V: Debug> Debug for FrozenFlatMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrozenFlatMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq, V> Clone for FrozenFlatMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<K: Eq, V> FrozenFlatMap<K, V> {
+    pub fn get(&self, k: &K) -> Option<&V> {
+        find_index(&self.inner, k).map(|i| self.inner[i].value())
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[FlatMapEntry<K, V>] {
+        &self.inner
+    }
+
+    /// bytes of the shared entries, frozen maps have no spare capacity
+    pub fn allocated_bytes(&self) -> usize {
+        size_of_val(&*self.inner)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.inner.iter()
+    }
+}
+
+impl<K: Eq, V> From<FlatMap<K, V>> for FrozenFlatMap<K, V> {
+    fn from(map: FlatMap<K, V>) -> Self {
+        Self {
+            inner: map.into_inner().into(),
+        }
+    }
+}
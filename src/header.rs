@@ -0,0 +1,91 @@
+use std::fmt::Debug;
+
+use crate::FlatMultiMap;
+
+/// Multi value map with ASCII case-insensitive names, suitable for HTTP headers
+/// names keep the case they were inserted with
+pub struct FlatHeaderMap<V = String> {
+    inner: FlatMultiMap<String, V>,
+}
+
+impl<V: Debug> Debug for FlatHeaderMap<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatHeaderMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<V: Clone> Clone for FlatHeaderMap<V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<V> Default for FlatHeaderMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> FlatHeaderMap<V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: FlatMultiMap::with_capacity(capacity),
+        }
+    }
+
+    /// first value for name
+    pub fn get(&self, name: &str) -> Option<&V> {
+        self.inner.get_by(|key| key.eq_ignore_ascii_case(name))
+    }
+
+    /// every value for name, in insertion order
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a V> {
+        self.inner
+            .get_all_by(move |key| key.eq_ignore_ascii_case(name))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// adds a value for name, keeping existing values
+    pub fn append(&mut self, name: impl Into<String>, value: V) {
+        self.inner.append(name.into(), value);
+    }
+
+    /// replaces every value of name with value, returns the replaced values
+    pub fn insert(&mut self, name: impl Into<String>, value: V) -> Vec<V> {
+        let name = name.into();
+        let removed = self.delete(&name);
+        self.inner.append(name, value);
+        removed
+    }
+
+    /// removes every value of name
+    pub fn delete(&mut self, name: &str) -> Vec<V> {
+        self.inner.delete_by(|key| key.eq_ignore_ascii_case(name))
+    }
+
+    /// number of values, counting repeated names
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &V)> {
+        self.inner
+            .iter()
+            .map(|entry| (entry.key().as_str(), entry.value()))
+    }
+}
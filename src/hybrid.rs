@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::FlatMap;
+
+/// default length past which a HybridMap migrates to hashing
+pub const DEFAULT_HYBRID_THRESHOLD: usize = 32;
+
+enum Storage<K: Eq, V> {
+    Flat(FlatMap<K, V>),
+    Hashed(HashMap<K, V>),
+}
+
+/// Map that starts as a FlatMap and migrates to a HashMap once len() crosses the threshold
+/// migration is one way, the map stays hashed even if it shrinks again
+pub struct HybridMap<K: Eq + Hash, V> {
+    storage: Storage<K, V>,
+    threshold: usize,
+}
+
+impl<K: Eq + Hash + Debug, V: Debug> Debug for HybridMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("HybridMap");
+        match &self.storage {
+            Storage::Flat(map) => s.field("flat", map),
+            Storage::Hashed(map) => s.field("hashed", map),
+        };
+        s.field("threshold", &self.threshold).finish()
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Clone for HybridMap<K, V> {
+    fn clone(&self) -> Self {
+        let storage = match &self.storage {
+            Storage::Flat(map) => Storage::Flat(map.clone()),
+            Storage::Hashed(map) => Storage::Hashed(map.clone()),
+        };
+        Self {
+            storage,
+            threshold: self.threshold,
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Default for HybridMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, V> HybridMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_HYBRID_THRESHOLD)
+    }
+
+    /// map migrates to hashing when len() grows beyond threshold
+    pub fn with_threshold(threshold: usize) -> Self {
+        Self {
+            storage: Storage::Flat(FlatMap::new()),
+            threshold,
+        }
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// returns true once storage has migrated to a HashMap
+    pub fn is_hashed(&self) -> bool {
+        matches!(self.storage, Storage::Hashed(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.storage {
+            Storage::Flat(map) => map.len(),
+            Storage::Hashed(map) => map.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        match &self.storage {
+            Storage::Flat(map) => map.get(k),
+            Storage::Hashed(map) => map.get(k),
+        }
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let old = match &mut self.storage {
+            Storage::Flat(map) => map.insert(k, v),
+            Storage::Hashed(map) => return map.insert(k, v),
+        };
+
+        if self.len() > self.threshold {
+            self.migrate();
+        }
+
+        old
+    }
+
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        match &mut self.storage {
+            Storage::Flat(map) => map.delete(k),
+            Storage::Hashed(map) => map.remove(k),
+        }
+    }
+
+    pub fn iter(&self) -> HybridIter<'_, K, V> {
+        match &self.storage {
+            Storage::Flat(map) => HybridIter::Flat(map.as_slice().iter()),
+            Storage::Hashed(map) => HybridIter::Hashed(map.iter()),
+        }
+    }
+
+    fn migrate(&mut self) {
+        if let Storage::Flat(map) = &mut self.storage {
+            let flat = std::mem::take(map);
+            self.storage = Storage::Hashed(flat.into_iter().collect());
+        }
+    }
+}
+
+pub enum HybridIter<'a, K, V> {
+    Flat(std::slice::Iter<'a, crate::FlatMapEntry<K, V>>),
+    Hashed(std::collections::hash_map::Iter<'a, K, V>),
+}
+
+impl<'a, K, V> Iterator for HybridIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Flat(iter) => iter.next().map(|entry| (entry.key(), entry.value())),
+            Self::Hashed(iter) => iter.next(),
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Debug;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// Linear Map with an optional sorted index for read heavy phases
+/// after IndexedFlatMap::rebuild_index lookups binary search the index,
+/// inserting a new key or deleting drops it and lookups fall back to a linear scan
+pub struct IndexedFlatMap<K: Ord, V> {
+    inner: FlatMap<K, V>,
+    // positions into inner ordered by key
+    index: Option<Vec<u32>>,
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for IndexedFlatMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IndexedFlatMap")
+            .field("inner", &self.inner)
+            .field("indexed", &self.index.is_some())
+            .finish()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Clone for IndexedFlatMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            index: self.index.clone(),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for IndexedFlatMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> IndexedFlatMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: FlatMap::with_capacity(capacity),
+            index: None,
+        }
+    }
+
+    /// sorts entry positions by key so lookups run in O(log n) until the next insert of a new key or delete
+    /// panics if the map holds more than u32::MAX entries
+    pub fn rebuild_index(&mut self) {
+        let len = u32::try_from(self.inner.len())
+            .expect("IndexedFlatMap index holds at most u32::MAX entries");
+        let entries = self.inner.as_slice();
+        let mut index: Vec<u32> = (0..len).collect();
+        index.sort_unstable_by(|&a, &b| entries[a as usize].key().cmp(entries[b as usize].key()));
+        self.index = Some(index);
+    }
+
+    /// true while lookups use the sorted index
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+
+    pub fn get_index_of(&self, k: &K) -> Option<usize> {
+        let Some(index) = &self.index else {
+            return self.inner.get_index_of(k);
+        };
+        let entries = self.inner.as_slice();
+        index
+            .binary_search_by(|&i| entries[i as usize].key().cmp(k))
+            .ok()
+            .map(|i| index[i] as usize)
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        let i = self.get_index_of(k)?;
+        self.inner.get_index(i).map(|entry| entry.value())
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.get_index_of(k).is_some()
+    }
+
+    /// replacing the value of an existing key keeps the index
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        if let Some(i) = self.get_index_of(&k) {
+            let (_, value) = self.inner.get_index_mut(i).unwrap();
+            return Some(std::mem::replace(value, v));
+        }
+        self.index = None;
+        self.inner.insert(k, v)
+    }
+
+    /// modifies the value of k in place, keeps the index
+    pub fn update(&mut self, k: &K, f: impl FnOnce(&mut V)) -> bool {
+        let Some(i) = self.get_index_of(k) else {
+            return false;
+        };
+        f(self.inner.get_index_mut(i).unwrap().1);
+        true
+    }
+
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let v = self.inner.delete(k)?;
+        self.index = None;
+        Some(v)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// entries in storage order, not key order
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.inner.iter()
+    }
+
+    pub fn as_map(&self) -> &FlatMap<K, V> {
+        &self.inner
+    }
+
+    pub fn into_map(self) -> FlatMap<K, V> {
+        self.inner
+    }
+}
+
+impl<K: Ord, V> From<FlatMap<K, V>> for IndexedFlatMap<K, V> {
+    fn from(inner: FlatMap<K, V>) -> Self {
+        Self { inner, index: None }
+    }
+}
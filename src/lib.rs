@@ -1,5 +1,70 @@
+mod batch;
+mod builder;
+mod bytes;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+mod compact_str;
+mod counter;
+#[cfg(any(feature = "codegen", feature = "csv"))]
+mod csv;
+pub mod diagnostics;
+mod duplicates;
+pub mod encode;
+mod entry;
+mod enum_map;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod frozen;
+mod header;
+mod hybrid;
+mod indexed;
 mod map;
+mod map2;
+mod merge;
+mod multimap;
+mod normalized;
+mod observed;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod priority;
+mod ptr_eq;
+mod range;
+mod scope;
 mod set;
+mod stable;
+mod storage;
+mod traits;
+#[cfg(feature = "urlencoded")]
+mod urlencoded;
+mod view;
 
-pub use map::{ConstantFlatMap, FlatMap, FlatMapEntry};
+pub use batch::{BatchError, BatchOp};
+pub use builder::{DuplicatePolicy, FlatMapBuilder};
+pub use bytes::FlatByteMap;
+pub use compact_str::CompactStrFlatMap;
+pub use counter::FlatCounter;
+pub use duplicates::DuplicateKeyError;
+pub use entry::{EntryRef, OccupiedEntryRef, VacantEntryRef};
+pub use enum_map::{EnumFlatMap, FlatMapKey};
+pub use frozen::FrozenFlatMap;
+pub use header::FlatHeaderMap;
+pub use hybrid::{DEFAULT_HYBRID_THRESHOLD, HybridIter, HybridMap};
+pub use indexed::IndexedFlatMap;
+pub use map::{ConstantFlatMap, EntryIndex, FlatMap, FlatMapEntry, KeyExists};
+pub use map2::FlatMap2;
+pub use merge::NotSortedError;
+pub use multimap::FlatMultiMap;
+pub use normalized::{KeyNormalizer, NormalizedFlatMap, TrimLowercase};
+pub use observed::{MapChange, ObservedFlatMap};
+pub use priority::FlatPriorityMap;
+pub use ptr_eq::PtrEq;
+pub use range::FlatRangeMap;
+pub use scope::ScopeGuard;
 pub use set::{ConstantFlatSet, FlatSet};
+pub use stable::StableFlatMap;
+pub use storage::{
+    BufferFlatMap, BufferFlatSet, BufferStorage, FlatStorage, FlatStorageMut, GrowableStorage,
+    GrowthStrategy, InlineFlatMap, InlineFlatSet, InlineStorage, ShrinkPolicy,
+};
+pub use traits::{FlatMapRead, FlatMapWrite, FlatSetRead, FlatSetWrite};
+pub use view::{FlatMapView, FlatSetView};
@@ -1,5 +1,64 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::hash::Hash;
+use std::marker::PhantomData;
 
+use crate::FrozenFlatMap;
+use crate::duplicates::{
+    DuplicateKeyError, find_duplicate, find_duplicate_groups, find_duplicate_ord,
+};
+use crate::storage::{FlatStorage, FlatStorageMut, GrowableStorage, GrowthStrategy, ShrinkPolicy};
+
+/// error from renaming a key onto one that is already present
+/// holds the rejected key and the index of the entry that has it
+pub struct KeyExists<K> {
+    pub key: K,
+    pub index: usize,
+}
+
+impl<K: Debug> Debug for KeyExists<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyExists")
+            .field("key", &self.key)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
+impl<K> std::fmt::Display for KeyExists<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "key already exists at index {}", self.index)
+    }
+}
+
+impl<K: Debug> std::error::Error for KeyExists<K> {}
+
+/// finds the index of old and of new in a single pass
+pub(crate) fn find_rename<T, K: Eq>(
+    items: &[T],
+    key: impl Fn(&T) -> &K,
+    old: &K,
+    new: &K,
+) -> (Option<usize>, Option<usize>) {
+    let mut old_index = None;
+    let mut new_index = None;
+    for (i, item) in items.iter().enumerate() {
+        let k = key(item);
+        if old_index.is_none() && k == old {
+            old_index = Some(i);
+        } else if new_index.is_none() && k == new {
+            new_index = Some(i);
+        }
+        if old_index.is_some() && new_index.is_some() {
+            break;
+        }
+    }
+    (old_index, new_index)
+}
+
+/// laid out as repr(C) so tables of plain data entries can be reinterpreted from bytes,
+/// see FlatMapView::from_bytes_unchecked
+#[repr(C)]
 pub struct FlatMapEntry<K, V> {
     key: K,
     value: V,
@@ -24,6 +83,10 @@ impl<K, V> FlatMapEntry<K, V> {
     pub fn value_mut(&mut self) -> &mut V {
         &mut self.value
     }
+
+    pub(crate) fn parts_mut(&mut self) -> (&K, &mut V) {
+        (&self.key, &mut self.value)
+    }
 }
 
 impl<K: Debug, V: Debug> Debug for FlatMapEntry<K, V> {
@@ -42,6 +105,11 @@ impl<K: Clone, V: Clone> Clone for FlatMapEntry<K, V> {
             value: self.value.clone(),
         }
     }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.key.clone_from(&source.key);
+        self.value.clone_from(&source.value);
+    }
 }
 
 impl<K, V> From<(K, V)> for FlatMapEntry<K, V> {
@@ -50,56 +118,132 @@ impl<K, V> From<(K, V)> for FlatMapEntry<K, V> {
     }
 }
 
-impl<K, V> Into<(K, V)> for FlatMapEntry<K, V> {
-    fn into(self) -> (K, V) {
-        (self.key, self.value)
+impl<K, V> From<FlatMapEntry<K, V>> for (K, V) {
+    fn from(entry: FlatMapEntry<K, V>) -> Self {
+        (entry.key, entry.value)
     }
 }
 
 /// Linear Map with no sorting guarantee and no duplicate entries
-pub struct FlatMap<K: Eq, V> {
-    inner: Vec<FlatMapEntry<K, V>>,
+/// storage defaults to a Vec, see FlatStorage for inline and frozen backends
+pub struct FlatMap<K: Eq, V, S = Vec<FlatMapEntry<K, V>>> {
+    pub(crate) inner: S,
+    shrink: Option<ShrinkPolicy>,
+    growth: GrowthStrategy,
+    /// bumped whenever entries move, debug builds only
+    #[cfg(debug_assertions)]
+    generation: u64,
+    marker: PhantomData<FlatMapEntry<K, V>>,
+}
+
+/// position of an entry handed out by FlatMap::entry_index
+/// debug builds remember the map's generation and panic if the index is used after entries moved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryIndex {
+    index: usize,
+    #[cfg(debug_assertions)]
+    generation: u64,
 }
 
-impl<K: Eq + Debug, V: Debug> Debug for FlatMap<K, V> {
+impl EntryIndex {
+    pub const fn get(&self) -> usize {
+        self.index
+    }
+}
+
+impl<K: Eq + Debug, V: Debug, S: FlatStorage<Item = FlatMapEntry<K, V>>> Debug
+    for FlatMap<K, V, S>
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FlatMap")
-            .field("inner", &self.inner)
+            .field("inner", &self.inner.as_slice())
             .finish()
     }
 }
 
-impl<K: Eq + Clone, V: Clone> Clone for FlatMap<K, V> {
+impl<K: Eq, V, S: Clone> Clone for FlatMap<K, V, S> {
     fn clone(&self) -> Self {
         Self {
-            inner: self.inner.clone(),
+            shrink: self.shrink,
+            growth: self.growth,
+            ..Self::from_inner(self.inner.clone())
         }
     }
+
+    /// reuses the allocation of self, so repeated snapshots into the same map don't allocate
+    fn clone_from(&mut self, source: &Self) {
+        self.inner.clone_from(&source.inner);
+        self.invalidate_indices();
+        self.shrink = source.shrink;
+        self.growth = source.growth;
+    }
 }
 
-impl<K: Eq, V> Default for FlatMap<K, V> {
+impl<K: Eq, V, S: FlatStorage<Item = FlatMapEntry<K, V>> + Default> Default for FlatMap<K, V, S> {
     fn default() -> Self {
-        Self::new()
+        Self::from_inner(S::default())
     }
 }
 
-impl<K: Eq, V> FlatMap<K, V>
-where
-    K: Eq,
-{
+impl<K: Eq, V, S> FlatMap<K, V, S> {
+    pub(crate) const fn from_inner(inner: S) -> Self {
+        Self {
+            inner,
+            shrink: None,
+            growth: GrowthStrategy::Amortized,
+            #[cfg(debug_assertions)]
+            generation: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// called whenever entries move, so stale EntryIndex values are caught in debug builds
+    pub(crate) fn invalidate_indices(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            self.generation += 1;
+        }
+    }
+
+    fn make_index(&self, index: usize) -> EntryIndex {
+        EntryIndex {
+            index,
+            #[cfg(debug_assertions)]
+            generation: self.generation,
+        }
+    }
+
+    fn check_index(&self, index: EntryIndex) -> usize {
+        #[cfg(debug_assertions)]
+        assert_eq!(
+            index.generation, self.generation,
+            "stale EntryIndex {}, entries were moved or replaced after it was taken",
+            index.index
+        );
+        index.index
+    }
+}
+
+impl<K: Eq, V> FlatMap<K, V> {
     pub fn new() -> Self {
         Self::with_capacity(0)
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            inner: Vec::with_capacity(capacity),
-        }
+        Self::from_inner(Vec::with_capacity(capacity))
+    }
+
+    /// preallocates capacity and grows by growth once that is used up
+    pub fn with_capacity_and_growth(capacity: usize, growth: GrowthStrategy) -> Self {
+        let mut s = Self::with_capacity(capacity);
+        s.growth = growth;
+        s
     }
 
     /// inserts entries by checking for duplicates for every item
     /// if duplicate items are found, last one stays while earlier gets discarded
     /// not recommended for large list of entries, check for duplicates yourself and use FlatMap::from_entries_unchecked
+    /// or use FlatMap::from_entries_dedup_last when keys are Ord, FlatMap::from_entries_hashed when keys are Hash
     pub fn from_entries(iter: impl Iterator<Item = FlatMapEntry<K, V>>) -> Self {
         let (cap, _) = iter.size_hint();
         let mut s = Self::with_capacity(cap);
@@ -109,58 +253,661 @@ where
         s
     }
 
+    /// same semantics as FlatMap::from_entries, last duplicate stays,
+    /// but allocates exactly once for iter.len() entries, duplicates leave spare capacity
+    pub fn from_exact_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = FlatMapEntry<K, V>, IntoIter: ExactSizeIterator>,
+    {
+        let iter = iter.into_iter();
+        let mut s = Self::with_capacity(iter.len());
+        for entry in iter {
+            s.insert(entry.key, entry.value);
+        }
+        s
+    }
+
+    /// builds a map from an array literal, last duplicate stays
+    /// stands in for From<[(K, V); N]>, which would overlap the From<Iterator> impl
+    pub fn from_array<const N: usize>(entries: [(K, V); N]) -> Self {
+        Self::from_exact_iter(entries.into_iter().map(FlatMapEntry::from))
+    }
+
+    /// same semantics as FlatMap::from_entries, last duplicate stays,
+    /// but sorts by key and deduplicates in O(n log n), suitable for large inputs
+    /// resulting entries are in ascending key order
+    pub fn from_entries_dedup_last(iter: impl Iterator<Item = FlatMapEntry<K, V>>) -> Self
+    where
+        K: Ord,
+    {
+        let mut inner: Vec<_> = iter.collect();
+        // reversing before the stable sort puts the last occurrence of each key first in its run
+        inner.reverse();
+        inner.sort_by(|a, b| a.key.cmp(&b.key));
+        inner.dedup_by(|a, b| a.key == b.key);
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        Self::from_inner(inner)
+    }
+
+    /// same semantics as FlatMap::from_entries, last duplicate stays,
+    /// but deduplicates in O(n) with a temporary hash map of key indices
+    pub fn from_entries_hashed(iter: impl Iterator<Item = FlatMapEntry<K, V>>) -> Self
+    where
+        K: Hash,
+    {
+        let mut inner: Vec<_> = iter.collect();
+
+        let keep = {
+            let mut last = HashMap::with_capacity(inner.len());
+            for (i, entry) in inner.iter().enumerate() {
+                last.insert(&entry.key, i);
+            }
+            let mut keep = vec![false; inner.len()];
+            for i in last.into_values() {
+                keep[i] = true;
+            }
+            keep
+        };
+
+        let mut i = 0;
+        inner.retain(|_| {
+            i += 1;
+            keep[i - 1]
+        });
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        Self::from_inner(inner)
+    }
+
     /// construct inner vec without checking for duplicates
+    /// debug builds still verify the invariant and panic on duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two entries have equal keys
     pub unsafe fn from_entries_unchecked(iter: impl Iterator<Item = FlatMapEntry<K, V>>) -> Self {
-        Self {
-            inner: iter.collect(),
+        unsafe { Self::from_vec_unchecked(iter.collect()) }
+    }
+
+    /// construct map from a vec of entries without checking for duplicates
+    /// debug builds still verify the invariant and panic on duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two entries have equal keys
+    pub unsafe fn from_vec_unchecked(inner: Vec<FlatMapEntry<K, V>>) -> Self {
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        let s = Self::from_inner(inner);
+        #[cfg(debug_assertions)]
+        s.assert_invariants();
+        s
+    }
+
+    /// construct map from entries already in strictly ascending key order, without sorting or checking in release
+    /// strictly ascending keys can't contain duplicates, so this is O(n),
+    /// debug builds still verify the order and panic if it doesn't hold
+    ///
+    /// # Safety
+    /// caller must guarantee that every key is greater than the one before it
+    pub unsafe fn from_sorted_vec_unchecked(inner: Vec<FlatMapEntry<K, V>>) -> Self
+    where
+        K: Ord,
+    {
+        #[cfg(debug_assertions)]
+        if let Some(i) = inner.windows(2).position(|w| w[0].key >= w[1].key) {
+            panic!("FlatMap entries are not sorted at index {}", i + 1);
+        }
+        crate::diagnostics::grew_to("FlatMap", inner.len());
+        Self::from_inner(inner)
+    }
+
+    /// construct map from entries in strictly ascending key order, verifying the order as it goes in O(n)
+    /// on failure returns the index of the first entry whose key isn't greater than the previous one
+    pub fn from_sorted_iter(iter: impl Iterator<Item = FlatMapEntry<K, V>>) -> Result<Self, usize>
+    where
+        K: Ord,
+    {
+        let mut inner: Vec<FlatMapEntry<K, V>> = Vec::with_capacity(iter.size_hint().0);
+        for entry in iter {
+            if inner.last().is_some_and(|last| last.key >= entry.key) {
+                return Err(inner.len());
+            }
+            inner.push(entry);
+        }
+        Ok(Self::from_inner(inner))
+    }
+
+    pub fn into_inner(self) -> Vec<FlatMapEntry<K, V>> {
+        self.inner
+    }
+
+    /// splits into the entries f accepts and the rest in one pass, both keep their order
+    pub fn partition(self, mut f: impl FnMut(&K, &V) -> bool) -> (Self, Self) {
+        let (accepted, rejected) = self
+            .inner
+            .into_iter()
+            .partition(|entry| f(&entry.key, &entry.value));
+        // subsets of unique keys stay unique
+        (Self::from_inner(accepted), Self::from_inner(rejected))
+    }
+
+    /// converts into an immutable map without spare capacity that is cheap to clone and share
+    pub fn freeze(self) -> FrozenFlatMap<K, V> {
+        FrozenFlatMap::from(self)
+    }
+
+    /// swaps keys and values, if two values are equal returns the indices of the colliding entries
+    pub fn invert(self) -> Result<FlatMap<V, K>, DuplicateKeyError>
+    where
+        V: Eq,
+    {
+        for i in 0..self.inner.len() {
+            for j in (i + 1)..self.inner.len() {
+                if self.inner[i].value == self.inner[j].value {
+                    return Err(DuplicateKeyError::new(i, j));
+                }
+            }
         }
+
+        let inverted = self
+            .inner
+            .into_iter()
+            .map(|entry| FlatMapEntry::new(entry.value, entry.key))
+            .collect();
+        Ok(FlatMap::from_inner(inverted))
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+
+    /// keeps the entries f returns true for, preserving their order
+    /// stops at the first error, entries visited before it are already removed or kept
+    pub fn try_retain<E>(
+        &mut self,
+        mut f: impl FnMut(&K, &mut V) -> Result<bool, E>,
+    ) -> Result<(), E> {
+        let mut result = Ok(());
+        self.invalidate_indices();
+        self.inner.retain_mut(|entry| {
+            if result.is_err() {
+                return true;
+            }
+            match f(&entry.key, &mut entry.value) {
+                Ok(keep) => keep,
+                Err(e) => {
+                    result = Err(e);
+                    true
+                }
+            }
+        });
+        self.apply_shrink_policy();
+        result
+    }
+}
+
+impl<K: Eq, V, S: FlatStorage<Item = FlatMapEntry<K, V>>> FlatMap<K, V, S> {
+    /// map over any storage backend, checks for duplicates in O(n^2)
+    /// if found will return the indices of duplicate
+    pub fn from_storage(storage: S) -> Result<Self, DuplicateKeyError> {
+        let s = Self::from_inner(storage);
+        s.check_duplicates()?;
+        Ok(s)
+    }
+
+    /// map over any storage backend without checking for duplicates
+    /// debug builds still verify the invariant and panic on duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two entries have equal keys
+    pub unsafe fn from_storage_unchecked(storage: S) -> Self {
+        let s = Self::from_inner(storage);
+        #[cfg(debug_assertions)]
+        s.assert_invariants();
+        s
+    }
+
+    pub fn into_storage(self) -> S {
+        self.inner
+    }
+
+    /// bytes reserved for entries including spare capacity
+    /// heap memory owned by keys or values, e.g. String contents, isn't included
+    pub fn allocated_bytes(&self) -> usize {
+        self.inner.allocated_bytes()
+    }
+
+    /// checks that no two entries have equal keys, if found will return the indices of duplicate
+    pub fn check_duplicates(&self) -> Result<(), DuplicateKeyError> {
+        match find_duplicate(self.as_slice(), |a, b| a.key == b.key) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// full audit after unchecked construction or raw slice edits, O(n²)
+    /// every group lists the indices of entries sharing a key, empty if the invariant holds
+    pub fn duplicate_groups(&self) -> Vec<Vec<usize>> {
+        find_duplicate_groups(self.as_slice(), |a, b| a.key == b.key)
+    }
+
+    /// panics if two entries have equal keys
+    pub fn assert_invariants(&self) {
+        if let Err(DuplicateKeyError {
+            first: i,
+            second: j,
+        }) = self.check_duplicates()
+        {
+            panic!("FlatMap has duplicate keys at indices {i} and {j}");
+        }
+    }
+
+    pub fn as_slice(&self) -> &[FlatMapEntry<K, V>] {
+        self.inner.as_slice()
     }
 
     pub fn get(&self, k: &K) -> Option<&V> {
-        for entry in &self.inner {
-            if &entry.key == k {
-                return Some(&entry.value);
+        find_index(self.as_slice(), k).map(|i| &self.as_slice()[i].value)
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        find_index(self.as_slice(), k).is_some()
+    }
+
+    /// the stored key equal to k, which may differ from k in fields Eq ignores
+    pub fn get_key(&self, k: &K) -> Option<&K> {
+        find_index(self.as_slice(), k).map(|i| &self.as_slice()[i].key)
+    }
+
+    /// stored key and value for k, like HashMap::get_key_value
+    pub fn get_key_value(&self, k: &K) -> Option<(&K, &V)> {
+        let entry = &self.as_slice()[find_index(self.as_slice(), k)?];
+        Some((&entry.key, &entry.value))
+    }
+
+    /// all keys mapped to a value equal to v
+    pub fn keys_for_value<'a>(&'a self, v: &'a V) -> impl Iterator<Item = &'a K>
+    where
+        V: PartialEq,
+    {
+        self.as_slice()
+            .iter()
+            .filter(move |entry| &entry.value == v)
+            .map(|entry| &entry.key)
+    }
+
+    pub fn contains_value(&self, v: &V) -> bool
+    where
+        V: PartialEq,
+    {
+        self.as_slice().iter().any(|entry| &entry.value == v)
+    }
+
+    /// looks up every key in a single scan of the storage, results are in the order of keys
+    pub fn get_many<'a>(&'a self, keys: &[&K]) -> Vec<Option<&'a V>> {
+        let mut found = vec![None; keys.len()];
+        let mut remaining = keys.len();
+
+        for entry in self.as_slice() {
+            if remaining == 0 {
+                break;
+            }
+            for (i, key) in keys.iter().enumerate() {
+                if found[i].is_none() && &entry.key == *key {
+                    found[i] = Some(&entry.value);
+                    remaining -= 1;
+                }
             }
         }
 
-        None
+        found
+    }
+
+    /// entry stored at index, indices are invalidated by deletes since they swap_remove
+    pub fn get_index(&self, index: usize) -> Option<&FlatMapEntry<K, V>> {
+        self.as_slice().get(index)
+    }
+
+    /// index the entry for k is stored at
+    /// see FlatMap::entry_index for an index that debug builds check for staleness
+    pub fn get_index_of(&self, k: &K) -> Option<usize> {
+        find_index(self.as_slice(), k)
+    }
+
+    /// index of the entry for k, using it after a delete moved entries panics in debug builds
+    pub fn entry_index(&self, k: &K) -> Option<EntryIndex> {
+        find_index(self.as_slice(), k).map(|i| self.make_index(i))
+    }
+
+    /// entry at an index from FlatMap::entry_index
+    /// panics in debug builds if entries moved since the index was taken
+    pub fn get_at(&self, index: EntryIndex) -> Option<&FlatMapEntry<K, V>> {
+        let i = self.check_index(index);
+        self.as_slice().get(i)
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.as_slice().iter()
+    }
+
+    /// entries whose key starts with prefix
+    pub fn iter_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'a FlatMapEntry<K, V>>
+    where
+        K: AsRef<str>,
+    {
+        self.as_slice()
+            .iter()
+            .filter(move |entry| entry.key.as_ref().starts_with(prefix))
     }
 
+    /// keys that start with prefix
+    pub fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a K>
+    where
+        K: AsRef<str>,
+    {
+        self.iter_prefix(prefix).map(|entry| &entry.key)
+    }
+
+    /// entry whose key is the longest prefix of query, as used by path routers
+    pub fn longest_prefix_match(&self, query: &str) -> Option<(&K, &V)>
+    where
+        K: AsRef<str>,
+    {
+        longest_prefix_match(self.as_slice(), query)
+    }
+
+    /// yields entries in ascending key order without reordering the map
+    /// sorts a scratch buffer of indices, so allocates once
+    pub fn iter_sorted(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>>
+    where
+        K: Ord,
+    {
+        let mut indices: Vec<usize> = (0..self.as_slice().len()).collect();
+        indices.sort_unstable_by(|&a, &b| self.as_slice()[a].key.cmp(&self.as_slice()[b].key));
+        indices.into_iter().map(|i| &self.as_slice()[i])
+    }
+
+    /// yields keys in ascending order without reordering the map
+    pub fn keys_sorted(&self) -> impl Iterator<Item = &K>
+    where
+        K: Ord,
+    {
+        self.iter_sorted().map(|entry| &entry.key)
+    }
+
+    /// entry with the smallest key, found in one scan
+    pub fn min_key(&self) -> Option<&FlatMapEntry<K, V>>
+    where
+        K: Ord,
+    {
+        self.as_slice().iter().min_by(|a, b| a.key.cmp(&b.key))
+    }
+
+    /// entry with the largest key, found in one scan
+    pub fn max_key(&self) -> Option<&FlatMapEntry<K, V>>
+    where
+        K: Ord,
+    {
+        self.as_slice().iter().max_by(|a, b| a.key.cmp(&b.key))
+    }
+
+    /// entry minimizing f, e.g. `map.min_by_key(|entry| *entry.value())` for the lowest score
+    pub fn min_by_key<B: Ord>(
+        &self,
+        mut f: impl FnMut(&FlatMapEntry<K, V>) -> B,
+    ) -> Option<&FlatMapEntry<K, V>> {
+        self.as_slice().iter().min_by_key(|entry| f(entry))
+    }
+
+    /// entry maximizing f, e.g. `map.max_by_key(|entry| *entry.value())` for the highest score
+    pub fn max_by_key<B: Ord>(
+        &self,
+        mut f: impl FnMut(&FlatMapEntry<K, V>) -> B,
+    ) -> Option<&FlatMapEntry<K, V>> {
+        self.as_slice().iter().max_by_key(|entry| f(entry))
+    }
+}
+
+impl<K: Eq, V, S: FlatStorageMut<Item = FlatMapEntry<K, V>>> FlatMap<K, V, S> {
+    fn entries_mut(&mut self) -> &mut [FlatMapEntry<K, V>] {
+        self.inner.as_mut_slice()
+    }
+
+    /// mutable access to the underlying storage, useful for custom sorting
+    ///
+    /// # Safety
+    /// caller must not introduce entries with equal keys through the slice
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [FlatMapEntry<K, V>] {
+        self.invalidate_indices();
+        self.inner.as_mut_slice()
+    }
+
+    /// key and mutable value at index, the key stays shared so it can't be changed into a duplicate
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.entries_mut()
+            .get_mut(index)
+            .map(FlatMapEntry::parts_mut)
+    }
+
+    /// key and mutable value at an index from FlatMap::entry_index
+    /// panics in debug builds if entries moved since the index was taken
+    pub fn get_at_mut(&mut self, index: EntryIndex) -> Option<(&K, &mut V)> {
+        let i = self.check_index(index);
+        self.entries_mut().get_mut(i).map(FlatMapEntry::parts_mut)
+    }
+
+    /// applies f to the value of k if present, returns whether k was found
+    pub fn update(&mut self, k: &K, f: impl FnOnce(&mut V)) -> bool {
+        match find_index(self.as_slice(), k) {
+            Some(i) => {
+                f(&mut self.entries_mut()[i].value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// renames the key old to new in place without moving its value
+    /// returns false if old is missing, renaming a key to itself is a no-op
+    pub fn replace_key(&mut self, old: &K, new: K) -> Result<bool, KeyExists<K>> {
+        match find_rename(self.as_slice(), FlatMapEntry::key, old, &new) {
+            (_, Some(index)) => Err(KeyExists { key: new, index }),
+            (Some(i), None) => {
+                self.entries_mut()[i].key = new;
+                Ok(true)
+            }
+            (None, None) => Ok(false),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut FlatMapEntry<K, V>> {
+        self.entries_mut().iter_mut()
+    }
+}
+
+impl<K: Eq, V, S: GrowableStorage<Item = FlatMapEntry<K, V>>> FlatMap<K, V, S> {
+    /// panics if the storage is full, see FlatMap::try_insert
     pub fn insert(&mut self, k: K, v: V) -> Option<V> {
-        for entry in &mut self.inner {
-            if &entry.key == &k {
-                let mut new_value = v;
-                std::mem::swap(&mut entry.value, &mut new_value);
-                return Some(new_value);
+        self.insert_full(k, v).1
+    }
+
+    /// same as insert but also returns the index the entry is stored at
+    pub fn insert_full(&mut self, k: K, v: V) -> (usize, Option<V>) {
+        if let Some(i) = find_index(self.as_slice(), &k) {
+            return (
+                i,
+                Some(std::mem::replace(&mut self.entries_mut()[i].value, v)),
+            );
+        }
+
+        self.push_entry(FlatMapEntry::new(k, v));
+
+        (self.len() - 1, None)
+    }
+
+    /// same as insert but hands k and v back instead of panicking when the storage is full
+    pub fn try_insert(&mut self, k: K, v: V) -> Result<Option<V>, (K, V)> {
+        if let Some(i) = find_index(self.as_slice(), &k) {
+            return Ok(Some(std::mem::replace(&mut self.entries_mut()[i].value, v)));
+        }
+
+        self.grow_for_push();
+        match self.inner.try_push(FlatMapEntry::new(k, v)) {
+            Ok(()) => {
+                crate::diagnostics::grew_to("FlatMap", self.len());
+                Ok(None)
             }
+            Err(entry) => Err((entry.key, entry.value)),
         }
+    }
 
-        self.inner.push(FlatMapEntry::new(k, v));
+    /// appends an entry whose key is known to be missing
+    /// panics if the storage is full
+    pub(crate) fn push_entry(&mut self, entry: FlatMapEntry<K, V>) {
+        self.grow_for_push();
+        if self.inner.try_push(entry).is_err() {
+            panic!("FlatMap storage is full");
+        }
+        crate::diagnostics::grew_to("FlatMap", self.len());
+    }
 
-        None
+    /// returns the value for k, inserting the result of f first if k is missing, in one scan
+    pub fn get_or_insert_with(&mut self, k: K, f: impl FnOnce() -> V) -> &mut V {
+        let i = match find_index(self.as_slice(), &k) {
+            Some(i) => i,
+            None => {
+                self.push_entry(FlatMapEntry::new(k, f()));
+                self.len() - 1
+            }
+        };
+
+        &mut self.entries_mut()[i].value
+    }
+
+    /// applies update_fn to the value of k if present, otherwise inserts the result of insert_fn
+    pub fn upsert(&mut self, k: K, insert_fn: impl FnOnce() -> V, update_fn: impl FnOnce(&mut V)) {
+        match find_index(self.as_slice(), &k) {
+            Some(i) => update_fn(&mut self.entries_mut()[i].value),
+            None => self.push_entry(FlatMapEntry::new(k, insert_fn())),
+        }
     }
 
     pub fn delete(&mut self, k: &K) -> Option<V> {
-        for i in 0..self.inner.len() {
-            if &self.inner[i].key == k {
-                let value = self.inner.swap_remove(i);
-                return Some(value.value);
+        let i = find_index(self.as_slice(), k)?;
+        Some(self.swap_remove_index(i).value)
+    }
+
+    /// removes the entry at index, the last entry takes its place
+    pub(crate) fn swap_remove_index(&mut self, i: usize) -> FlatMapEntry<K, V> {
+        self.invalidate_indices();
+        let entry = self.inner.swap_remove(i);
+        self.apply_shrink_policy();
+        entry
+    }
+
+    /// deletes every given key in one backward pass and returns the removed values
+    pub fn remove_many<'a>(&mut self, keys: impl IntoIterator<Item = &'a K>) -> Vec<V>
+    where
+        K: 'a,
+    {
+        let keys: Vec<&K> = keys.into_iter().collect();
+        let mut removed = Vec::new();
+
+        // walking backwards means swap_remove only moves entries that were already visited
+        for i in (0..self.len()).rev() {
+            if keys.contains(&&self.as_slice()[i].key) {
+                self.invalidate_indices();
+                removed.push(self.inner.swap_remove(i).value);
             }
         }
+        self.apply_shrink_policy();
 
-        None
+        removed
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
-        self.inner.iter()
+    /// inserts every entry of other, f resolves keys present in both from the current and the new value
+    /// other may yield entries or (K, V) pairs, so another FlatMap can be passed directly
+    /// stops at the first error, entries merged before it stay merged and the conflicting one is left as is
+    pub fn try_merge<E>(
+        &mut self,
+        other: impl IntoIterator<Item = impl Into<FlatMapEntry<K, V>>>,
+        mut f: impl FnMut(&K, &V, V) -> Result<V, E>,
+    ) -> Result<(), E> {
+        for entry in other {
+            let entry = entry.into();
+            match find_index(self.as_slice(), &entry.key) {
+                Some(i) => {
+                    let current = &self.as_slice()[i];
+                    let merged = f(&current.key, &current.value, entry.value)?;
+                    self.entries_mut()[i].value = merged;
+                }
+                None => self.push_entry(entry),
+            }
+        }
+        Ok(())
     }
 
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut FlatMapEntry<K, V>> {
-        self.inner.iter_mut()
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
     }
 
-    pub fn shrink_to_fit(&mut self) {
-        self.inner.shrink_to_fit();
+    /// opt in to releasing memory automatically after deletes, None turns it off
+    pub fn set_shrink_policy(&mut self, policy: Option<ShrinkPolicy>) {
+        self.shrink = policy;
+        self.apply_shrink_policy();
+    }
+
+    pub fn shrink_policy(&self) -> Option<ShrinkPolicy> {
+        self.shrink
+    }
+
+    /// changes how the storage grows on inserts that find it full
+    pub fn set_growth(&mut self, growth: GrowthStrategy) {
+        self.growth = growth;
+    }
+
+    pub fn growth(&self) -> GrowthStrategy {
+        self.growth
+    }
+
+    pub(crate) fn grow_for_push(&mut self) {
+        let len = self.inner.as_slice().len();
+        if len == self.inner.capacity()
+            && let Some(additional) = self.growth.additional(len)
+        {
+            self.inner.reserve_exact(additional);
+        }
+    }
+
+    pub(crate) fn apply_shrink_policy(&mut self) {
+        if let Some(target) = self
+            .shrink
+            .and_then(|policy| policy.target(self.len(), self.inner.capacity()))
+        {
+            self.inner.shrink_to(target);
+        }
+    }
+}
+
+impl<K: Eq, V> FromIterator<(K, V)> for FlatMap<K, V> {
+    /// last duplicate stays
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self::from_entries(iter.into_iter().map(FlatMapEntry::from))
+    }
+}
+
+impl<K: Eq, V> FromIterator<FlatMapEntry<K, V>> for FlatMap<K, V> {
+    /// last duplicate stays
+    fn from_iter<T: IntoIterator<Item = FlatMapEntry<K, V>>>(iter: T) -> Self {
+        Self::from_entries(iter.into_iter())
     }
 }
 
@@ -173,18 +920,36 @@ where
     }
 }
 
+impl<K: Eq, V> Extend<(K, V)> for FlatMap<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.inner.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Eq, V> Extend<FlatMapEntry<K, V>> for FlatMap<K, V> {
+    fn extend<T: IntoIterator<Item = FlatMapEntry<K, V>>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().map(<(K, V)>::from));
+    }
+}
+
+/// yields (K, V) like std maps, use FlatMap::into_inner for the entries themselves
 impl<K: Eq, V> IntoIterator for FlatMap<K, V> {
-    type Item = FlatMapEntry<K, V>;
+    type Item = (K, V);
 
     fn into_iter(self) -> Self::IntoIter {
-        self.inner.into_iter()
+        self.inner.into_iter().map(<(K, V)>::from)
     }
 
-    type IntoIter = std::vec::IntoIter<FlatMapEntry<K, V>>;
+    type IntoIter =
+        std::iter::Map<std::vec::IntoIter<FlatMapEntry<K, V>>, fn(FlatMapEntry<K, V>) -> (K, V)>;
 }
 
 pub struct ConstantFlatMap<K: Eq, V, const N: usize> {
-    inner: [FlatMapEntry<K, V>; N],
+    pub(crate) inner: [FlatMapEntry<K, V>; N],
 }
 
 impl<K: Eq, V, const N: usize> From<[FlatMapEntry<K, V>; N]> for ConstantFlatMap<K, V, N> {
@@ -219,21 +984,29 @@ impl<K: Eq + Clone, V: Clone, const N: usize> Clone for ConstantFlatMap<K, V, N>
 
 impl<K: Eq, V, const N: usize> ConstantFlatMap<K, V, N> {
     pub fn get(&self, key: &K) -> Option<&V> {
-        for entry in &self.inner {
-            if &entry.key == key {
-                return Some(&entry.value);
-            }
-        }
-        return None;
+        find_index(&self.inner, key).map(|i| &self.inner[i].value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        find_index(&self.inner, key).is_some()
+    }
+
+    /// entry whose key is the longest prefix of query, as used by path routers
+    pub fn longest_prefix_match(&self, query: &str) -> Option<(&K, &V)>
+    where
+        K: AsRef<str>,
+    {
+        longest_prefix_match(&self.inner, query)
     }
 
     /// checks for duplicates, if found will return the indices of duplicate
     /// not recommended for large list of entries, check for duplicates yourself and use ConstantFlatMap::from_entries_unchecked
-    pub fn from_entries(entries: [FlatMapEntry<K, V>; N]) -> Result<Self, (usize, usize)> {
+    /// or use ConstantFlatMap::from_entries_ord when keys are Ord
+    pub fn from_entries(entries: [FlatMapEntry<K, V>; N]) -> Result<Self, DuplicateKeyError> {
         for i in 0..N {
             for j in (i + 1)..N {
                 if entries[i].key == entries[j].key {
-                    return Err((i, j));
+                    return Err(DuplicateKeyError::new(i, j));
                 }
             }
         }
@@ -241,11 +1014,72 @@ impl<K: Eq, V, const N: usize> ConstantFlatMap<K, V, N> {
         Ok(unsafe { Self::from_entries_unchecked(entries) })
     }
 
+    /// same as ConstantFlatMap::from_entries, reporting the same indices, but checks in O(N log N)
+    pub fn from_entries_ord(entries: [FlatMapEntry<K, V>; N]) -> Result<Self, DuplicateKeyError>
+    where
+        K: Ord,
+    {
+        if let Some(e) = find_duplicate_ord(&entries, |entry| &entry.key) {
+            return Err(e);
+        }
+
+        Ok(unsafe { Self::from_entries_unchecked(entries) })
+    }
+
     /// construct Map without checking for duplicates
-    pub unsafe fn from_entries_unchecked(entries: [FlatMapEntry<K, V>; N]) -> Self {
+    /// this is a const fn so unlike FlatMap it can't verify the invariant in debug builds
+    ///
+    /// # Safety
+    /// caller must guarantee that no two entries have equal keys
+    pub const unsafe fn from_entries_unchecked(entries: [FlatMapEntry<K, V>; N]) -> Self {
         Self { inner: entries }
     }
 
+    /// checks that no two entries have equal keys, if found will return the indices of duplicate
+    pub fn check_duplicates(&self) -> Result<(), DuplicateKeyError> {
+        match find_duplicate(&self.inner, |a, b| a.key == b.key) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// panics if two entries have equal keys
+    pub fn assert_invariants(&self) {
+        if let Err(DuplicateKeyError {
+            first: i,
+            second: j,
+        }) = self.check_duplicates()
+        {
+            panic!("ConstantFlatMap has duplicate keys at indices {i} and {j}");
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    pub fn into_inner(self) -> [FlatMapEntry<K, V>; N] {
+        self.inner
+    }
+
+    /// merges two maps into one of length R, R must equal N + M, checked at compile time
+    /// checks for duplicates across both maps at runtime, if found will return the indices of duplicate in the merged map
+    /// for &'static str and integer keys ConstantFlatMap::concat_const also checks duplicates at compile time
+    pub fn concat<const M: usize, const R: usize>(
+        self,
+        other: ConstantFlatMap<K, V, M>,
+    ) -> Result<ConstantFlatMap<K, V, R>, DuplicateKeyError> {
+        const { assert!(R == N + M, "length of concatenated map must be N + M") };
+
+        let mut entries = self.inner.into_iter().chain(other.inner);
+        let merged = std::array::from_fn(|_| entries.next().unwrap());
+        ConstantFlatMap::from_entries(merged)
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
         self.inner.iter()
     }
@@ -254,3 +1088,158 @@ impl<K: Eq, V, const N: usize> ConstantFlatMap<K, V, N> {
         self.inner.iter_mut()
     }
 }
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+macro_rules! impl_concat_const {
+    ($(#[$doc:meta])* $k:ty, |$a:ident, $b:ident| $eq:expr) => {
+        impl<V: Copy, const N: usize> ConstantFlatMap<$k, V, N> {
+            /// same as ConstantFlatMap::concat but usable in const items,
+            /// where a duplicate fails the build once the result is unwrapped with a panic
+            $(#[$doc])*
+            pub const fn concat_const<const M: usize, const R: usize>(
+                &self,
+                other: &ConstantFlatMap<$k, V, M>,
+            ) -> Result<ConstantFlatMap<$k, V, R>, DuplicateKeyError> {
+                const { assert!(R == N + M, "length of concatenated map must be N + M") };
+
+                let mut merged =
+                    [const { std::mem::MaybeUninit::<FlatMapEntry<$k, V>>::uninit() }; R];
+                let mut i = 0;
+                while i < R {
+                    let entry = if i < N {
+                        &self.inner[i]
+                    } else {
+                        &other.inner[i - N]
+                    };
+                    merged[i] = std::mem::MaybeUninit::new(FlatMapEntry {
+                        key: entry.key,
+                        value: entry.value,
+                    });
+                    i += 1;
+                }
+                // every slot was written above and MaybeUninit<T> has the layout of T
+                let merged = unsafe {
+                    (&raw const merged)
+                        .cast::<[FlatMapEntry<$k, V>; R]>()
+                        .read()
+                };
+
+                let mut i = 0;
+                while i < R {
+                    let mut j = i + 1;
+                    while j < R {
+                        let ($a, $b) = (&merged[i].key, &merged[j].key);
+                        if $eq {
+                            return Err(DuplicateKeyError {
+                                first: i,
+                                second: j,
+                            });
+                        }
+                        j += 1;
+                    }
+                    i += 1;
+                }
+
+                Ok(ConstantFlatMap { inner: merged })
+            }
+        }
+    };
+}
+
+impl_concat_const!(
+    /// ```
+    /// use flatmap::{ConstantFlatMap, FlatMapEntry};
+    /// const BASE: ConstantFlatMap<&str, u8, 1> =
+    ///     unsafe { ConstantFlatMap::from_entries_unchecked([FlatMapEntry::new("if", 0)]) };
+    /// const EXT: ConstantFlatMap<&str, u8, 1> =
+    ///     unsafe { ConstantFlatMap::from_entries_unchecked([FlatMapEntry::new("then", 1)]) };
+    /// const ALL: ConstantFlatMap<&str, u8, 2> = match BASE.concat_const(&EXT) {
+    ///     Ok(all) => all,
+    ///     Err(_) => panic!("duplicate keyword"),
+    /// };
+    /// assert_eq!(ALL.get(&"then"), Some(&1));
+    /// ```
+    &'static str,
+    |a, b| str_eq(a, b)
+);
+impl_concat_const!(u8, |a, b| *a == *b);
+impl_concat_const!(u16, |a, b| *a == *b);
+impl_concat_const!(u32, |a, b| *a == *b);
+impl_concat_const!(u64, |a, b| *a == *b);
+impl_concat_const!(usize, |a, b| *a == *b);
+impl_concat_const!(i8, |a, b| *a == *b);
+impl_concat_const!(i16, |a, b| *a == *b);
+impl_concat_const!(i32, |a, b| *a == *b);
+impl_concat_const!(i64, |a, b| *a == *b);
+impl_concat_const!(isize, |a, b| *a == *b);
+
+/// yields (K, V) like std maps, use ConstantFlatMap::into_inner for the entries themselves
+impl<K: Eq, V, const N: usize> IntoIterator for ConstantFlatMap<K, V, N> {
+    type Item = (K, V);
+
+    type IntoIter = std::iter::Map<
+        std::array::IntoIter<FlatMapEntry<K, V>, N>,
+        fn(FlatMapEntry<K, V>) -> (K, V),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter().map(<(K, V)>::from)
+    }
+}
+
+/// index of the entry with key k
+/// keys that are small and have no drop glue, like integers, are compared four at a time
+/// without branching inside a chunk so the scan doesn't take one branch per entry
+pub(crate) fn find_index<K: Eq, V>(entries: &[FlatMapEntry<K, V>], k: &K) -> Option<usize> {
+    if std::mem::size_of::<K>() <= 8 && !std::mem::needs_drop::<K>() {
+        let mut chunks = entries.chunks_exact(4);
+        let mut offset = 0;
+        for chunk in &mut chunks {
+            let hits = u8::from(chunk[0].key == *k)
+                | u8::from(chunk[1].key == *k) << 1
+                | u8::from(chunk[2].key == *k) << 2
+                | u8::from(chunk[3].key == *k) << 3;
+            if hits != 0 {
+                return Some(offset + hits.trailing_zeros() as usize);
+            }
+            offset += 4;
+        }
+
+        return chunks
+            .remainder()
+            .iter()
+            .position(|entry| entry.key == *k)
+            .map(|i| offset + i);
+    }
+
+    entries.iter().position(|entry| entry.key == *k)
+}
+
+fn longest_prefix_match<'a, K: AsRef<str>, V>(
+    entries: &'a [FlatMapEntry<K, V>],
+    query: &str,
+) -> Option<(&'a K, &'a V)> {
+    let mut best: Option<&FlatMapEntry<K, V>> = None;
+    for entry in entries {
+        let key = entry.key.as_ref();
+        if query.starts_with(key) && best.is_none_or(|best| best.key.as_ref().len() < key.len()) {
+            best = Some(entry);
+        }
+    }
+
+    best.map(|entry| (&entry.key, &entry.value))
+}
@@ -0,0 +1,132 @@
+use std::fmt::Debug;
+
+use crate::FlatMapEntry;
+
+/// Linear Map keyed by a pair, looked up by both keys or by the first key alone
+pub struct FlatMap2<K1: Eq, K2: Eq, V> {
+    inner: Vec<FlatMapEntry<(K1, K2), V>>,
+}
+
+impl<K1: Eq + Debug, K2: Eq + Debug, V: Debug> Debug for FlatMap2<K1, K2, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatMap2")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K1: Eq + Clone, K2: Eq + Clone, V: Clone> Clone for FlatMap2<K1, K2, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K1: Eq, K2: Eq, V> Default for FlatMap2<K1, K2, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K1: Eq, K2: Eq, V> FlatMap2<K1, K2, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn find(&self, k1: &K1, k2: &K2) -> Option<usize> {
+        self.inner
+            .iter()
+            .position(|entry| entry.key().0 == *k1 && entry.key().1 == *k2)
+    }
+
+    pub fn get(&self, k1: &K1, k2: &K2) -> Option<&V> {
+        self.find(k1, k2).map(|i| self.inner[i].value())
+    }
+
+    pub fn get_mut(&mut self, k1: &K1, k2: &K2) -> Option<&mut V> {
+        self.find(k1, k2).map(|i| self.inner[i].value_mut())
+    }
+
+    pub fn contains_key(&self, k1: &K1, k2: &K2) -> bool {
+        self.find(k1, k2).is_some()
+    }
+
+    /// true if any entry has k1 as its first key
+    pub fn contains_k1(&self, k1: &K1) -> bool {
+        self.inner.iter().any(|entry| entry.key().0 == *k1)
+    }
+
+    pub fn insert(&mut self, k1: K1, k2: K2, v: V) -> Option<V> {
+        match self.find(&k1, &k2) {
+            Some(i) => Some(std::mem::replace(self.inner[i].value_mut(), v)),
+            None => {
+                self.inner.push(FlatMapEntry::new((k1, k2), v));
+                crate::diagnostics::grew_to("FlatMap2", self.inner.len());
+                None
+            }
+        }
+    }
+
+    pub fn delete(&mut self, k1: &K1, k2: &K2) -> Option<V> {
+        let i = self.find(k1, k2)?;
+        let (_, v) = self.inner.swap_remove(i).into();
+        Some(v)
+    }
+
+    /// removes every entry whose first key is k1, returns their second keys and values
+    pub fn delete_k1(&mut self, k1: &K1) -> Vec<(K2, V)> {
+        let mut removed = Vec::new();
+        let mut i = 0;
+        while i < self.inner.len() {
+            if self.inner[i].key().0 == *k1 {
+                let ((_, k2), v) = self.inner.swap_remove(i).into();
+                removed.push((k2, v));
+            } else {
+                i += 1;
+            }
+        }
+
+        removed
+    }
+
+    /// second keys and values of every entry whose first key is k1
+    pub fn iter_k1<'a>(&'a self, k1: &'a K1) -> impl Iterator<Item = (&'a K2, &'a V)> {
+        self.inner
+            .iter()
+            .filter(move |entry| entry.key().0 == *k1)
+            .map(|entry| (&entry.key().1, entry.value()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K1, &K2, &V)> {
+        self.inner
+            .iter()
+            .map(|entry| (&entry.key().0, &entry.key().1, entry.value()))
+    }
+}
+
+impl<K1: Eq, K2: Eq, V> FromIterator<(K1, K2, V)> for FlatMap2<K1, K2, V> {
+    /// last duplicate stays
+    fn from_iter<T: IntoIterator<Item = (K1, K2, V)>>(iter: T) -> Self {
+        let iter = iter.into_iter();
+        let mut map = Self::with_capacity(iter.size_hint().0);
+        for (k1, k2, v) in iter {
+            map.insert(k1, k2, v);
+        }
+        map
+    }
+}
@@ -0,0 +1,124 @@
+//! streaming k-way merge of maps whose entries are in ascending key order
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt::Display;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// front entry of one input, ordered so BinaryHeap pops the smallest key,
+/// equal keys pop in input order
+struct Head<K, V> {
+    entry: FlatMapEntry<K, V>,
+    source: usize,
+}
+
+impl<K: Ord, V> PartialEq for Head<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord, V> Eq for Head<K, V> {}
+
+impl<K: Ord, V> PartialOrd for Head<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, V> Ord for Head<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .entry
+            .key()
+            .cmp(self.entry.key())
+            .then(other.source.cmp(&self.source))
+    }
+}
+
+/// an input to FlatMap::merge_sorted isn't in ascending key order
+/// source is the input's position, position the index of its first key out of order
+/// converts into the (source, position) pair earlier versions returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotSortedError {
+    pub source: usize,
+    pub position: usize,
+}
+
+impl Display for NotSortedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "input {} is not in ascending key order at index {}",
+            self.source, self.position
+        )
+    }
+}
+
+impl std::error::Error for NotSortedError {}
+
+impl From<NotSortedError> for (usize, usize) {
+    fn from(e: NotSortedError) -> Self {
+        (e.source, e.position)
+    }
+}
+
+impl<K: Ord, V> FlatMap<K, V> {
+    /// merges maps whose entries are in ascending key order into one in ascending key order,
+    /// in O(total · log k) for k maps without sorting the combined entries
+    /// when several maps have a key, conflict combines the values in the order the maps were given
+    /// every input is checked before any entry is merged, if one isn't in ascending key order,
+    /// e.g. after a delete swapped entries, returns which input and where, and conflict is never called
+    pub fn merge_sorted(
+        maps: impl IntoIterator<Item = FlatMap<K, V>>,
+        mut conflict: impl FnMut(&K, V, V) -> V,
+    ) -> Result<Self, NotSortedError> {
+        let maps: Vec<_> = maps.into_iter().collect();
+        for (source, map) in maps.iter().enumerate() {
+            let entries = map.as_slice();
+            if let Some(i) = entries.windows(2).position(|w| w[0].key() >= w[1].key()) {
+                return Err(NotSortedError {
+                    source,
+                    position: i + 1,
+                });
+            }
+        }
+
+        let mut sources: Vec<_> = maps
+            .into_iter()
+            .map(|map| map.into_inner().into_iter())
+            .collect();
+        let total = sources.iter().map(|source| source.len()).sum();
+
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (source, entries) in sources.iter_mut().enumerate() {
+            if let Some(entry) = entries.next() {
+                heap.push(Head { entry, source });
+            }
+        }
+
+        let mut merged: Vec<FlatMapEntry<K, V>> = Vec::with_capacity(total);
+        while let Some(Head { entry, source }) = heap.pop() {
+            if let Some(next) = sources[source].next() {
+                heap.push(Head {
+                    entry: next,
+                    source,
+                });
+            }
+
+            // every input is ascending, so the heap yields keys in ascending order
+            match merged.last() {
+                Some(last) if last.key() == entry.key() => {
+                    let (k, prev) = merged.pop().unwrap().into();
+                    let (_, v) = entry.into();
+                    let value = conflict(&k, prev, v);
+                    merged.push(FlatMapEntry::new(k, value));
+                }
+                _ => merged.push(entry),
+            }
+        }
+
+        Ok(Self::from_inner(merged))
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::Debug;
+
+use crate::FlatMapEntry;
+
+/// Linear Map allowing several values per key, entries keep insertion order
+pub struct FlatMultiMap<K: Eq, V> {
+    inner: Vec<FlatMapEntry<K, V>>,
+}
+
+impl<K: Eq + Debug, V: Debug> Debug for FlatMultiMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatMultiMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq + Clone, V: Clone> Clone for FlatMultiMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Eq, V> Default for FlatMultiMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq, V> FlatMultiMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// first value stored for k
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.get_by(|key| key == k)
+    }
+
+    /// first value whose key matches, for lookups by something other than K's Eq
+    pub fn get_by(&self, mut matches: impl FnMut(&K) -> bool) -> Option<&V> {
+        self.inner
+            .iter()
+            .find(|entry| matches(entry.key()))
+            .map(|entry| entry.value())
+    }
+
+    /// every value stored for k, in insertion order
+    pub fn get_all<'a>(&'a self, k: &'a K) -> impl Iterator<Item = &'a V> {
+        self.get_all_by(move |key| key == k)
+    }
+
+    /// every value whose key matches, in insertion order
+    pub fn get_all_by<'a>(
+        &'a self,
+        mut matches: impl FnMut(&K) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a V> {
+        self.inner
+            .iter()
+            .filter(move |entry| matches(entry.key()))
+            .map(|entry| entry.value())
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.inner.iter().any(|entry| entry.key() == k)
+    }
+
+    /// adds a value for k, keeping existing values
+    pub fn append(&mut self, k: K, v: V) {
+        self.inner.push(FlatMapEntry::new(k, v));
+    }
+
+    /// replaces every value of k with v, returns the replaced values
+    pub fn insert(&mut self, k: K, v: V) -> Vec<V> {
+        let removed = self.delete(&k);
+        self.append(k, v);
+        removed
+    }
+
+    /// removes every value of k, keeping the order of remaining entries
+    pub fn delete(&mut self, k: &K) -> Vec<V> {
+        self.delete_by(|key| key == k)
+    }
+
+    /// removes every value whose key matches in a single pass, keeping the order of remaining entries
+    pub fn delete_by(&mut self, mut matches: impl FnMut(&K) -> bool) -> Vec<V> {
+        self.inner
+            .extract_if(.., |entry| matches(entry.key()))
+            .map(|entry| <(K, V)>::from(entry).1)
+            .collect()
+    }
+
+    /// number of entries, counting every value of a key
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.inner.iter()
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+}
+
+impl<K: Eq, V> Extend<(K, V)> for FlatMultiMap<K, V> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.inner.extend(iter.into_iter().map(FlatMapEntry::from));
+    }
+}
+
+impl<K: Eq, V> IntoIterator for FlatMultiMap<K, V> {
+    type Item = FlatMapEntry<K, V>;
+
+    type IntoIter = std::vec::IntoIter<FlatMapEntry<K, V>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
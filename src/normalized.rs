@@ -0,0 +1,122 @@
+use std::fmt::Debug;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// maps a key to its canonical form, NormalizedFlatMap stores and looks up only canonical keys
+/// implemented for every Fn(&K) -> K
+pub trait KeyNormalizer<K> {
+    fn normalize(&self, key: &K) -> K;
+}
+
+impl<K, F: Fn(&K) -> K> KeyNormalizer<K> for F {
+    fn normalize(&self, key: &K) -> K {
+        self(key)
+    }
+}
+
+/// trims surrounding whitespace and lowercases, for config keys and the like
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrimLowercase;
+
+impl KeyNormalizer<String> for TrimLowercase {
+    fn normalize(&self, key: &String) -> String {
+        key.trim().to_lowercase()
+    }
+}
+
+/// Linear Map that normalizes keys on every insert and lookup
+pub struct NormalizedFlatMap<K: Eq, V, N: KeyNormalizer<K>> {
+    inner: FlatMap<K, V>,
+    normalizer: N,
+}
+
+impl<K: Eq + Debug, V: Debug, N: KeyNormalizer<K>> Debug for NormalizedFlatMap<K, V, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NormalizedFlatMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq + Clone, V: Clone, N: KeyNormalizer<K> + Clone> Clone for NormalizedFlatMap<K, V, N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            normalizer: self.normalizer.clone(),
+        }
+    }
+}
+
+impl<K: Eq, V, N: KeyNormalizer<K> + Default> Default for NormalizedFlatMap<K, V, N> {
+    fn default() -> Self {
+        Self::new(N::default())
+    }
+}
+
+impl<K: Eq, V, N: KeyNormalizer<K>> NormalizedFlatMap<K, V, N> {
+    pub fn new(normalizer: N) -> Self {
+        Self::with_capacity(0, normalizer)
+    }
+
+    pub fn with_capacity(capacity: usize, normalizer: N) -> Self {
+        Self {
+            inner: FlatMap::with_capacity(capacity),
+            normalizer,
+        }
+    }
+
+    pub fn normalizer(&self) -> &N {
+        &self.normalizer
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(&self.normalizer.normalize(k))
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.get(k).is_some()
+    }
+
+    /// stores v under the normalized k, returns the previous value
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let k = self.normalizer.normalize(&k);
+        self.inner.insert(k, v)
+    }
+
+    pub fn update(&mut self, k: &K, f: impl FnOnce(&mut V)) -> bool {
+        let k = self.normalizer.normalize(k);
+        self.inner.update(&k, f)
+    }
+
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let k = self.normalizer.normalize(k);
+        self.inner.delete(&k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// entries with their normalized keys
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.inner.iter()
+    }
+
+    pub fn into_map(self) -> FlatMap<K, V> {
+        self.inner
+    }
+}
+
+impl<K: Eq, V, N: KeyNormalizer<K>> Extend<(K, V)> for NormalizedFlatMap<K, V, N> {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.inner.inner.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
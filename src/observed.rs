@@ -0,0 +1,91 @@
+use std::fmt::Debug;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// change reported to the observer of an ObservedFlatMap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapChange<'a, K> {
+    Inserted(&'a K),
+    Overwritten(&'a K),
+    Removed(&'a K),
+}
+
+/// FlatMap wrapper that calls observer with the affected key on every insert, overwrite and removal
+pub struct ObservedFlatMap<K: Eq, V, F: FnMut(MapChange<'_, K>)> {
+    map: FlatMap<K, V>,
+    observer: F,
+}
+
+impl<K: Eq + Debug, V: Debug, F: FnMut(MapChange<'_, K>)> Debug for ObservedFlatMap<K, V, F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObservedFlatMap")
+            .field("map", &self.map)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K: Eq, V, F: FnMut(MapChange<'_, K>)> ObservedFlatMap<K, V, F> {
+    pub fn new(observer: F) -> Self {
+        Self::from_map(FlatMap::new(), observer)
+    }
+
+    /// wraps an existing map, existing entries are not reported
+    pub fn from_map(map: FlatMap<K, V>, observer: F) -> Self {
+        Self { map, observer }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.map.get(k)
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.map.contains_key(k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.map.iter()
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        let (i, old) = self.map.insert_full(k, v);
+        let key = self.map.get_index(i).unwrap().key();
+        match old {
+            Some(_) => (self.observer)(MapChange::Overwritten(key)),
+            None => (self.observer)(MapChange::Inserted(key)),
+        }
+        old
+    }
+
+    /// applies f to the value of k if present and reports it as overwritten
+    pub fn update(&mut self, k: &K, f: impl FnOnce(&mut V)) -> bool {
+        let found = self.map.update(k, f);
+        if found {
+            (self.observer)(MapChange::Overwritten(k));
+        }
+        found
+    }
+
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let old = self.map.delete(k);
+        if old.is_some() {
+            (self.observer)(MapChange::Removed(k));
+        }
+        old
+    }
+
+    pub fn as_map(&self) -> &FlatMap<K, V> {
+        &self.map
+    }
+
+    pub fn into_map(self) -> FlatMap<K, V> {
+        self.map
+    }
+}
@@ -0,0 +1,154 @@
+//! parallel bulk construction on std threads
+//!
+//! keys are hashed and split into one bucket per thread in parallel chunks, then every thread
+//! deduplicates the keys of its bucket, equal keys always share a bucket so no merge between threads is needed
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::thread;
+
+use crate::{FlatMap, FlatMapEntry};
+
+fn thread_count(len: usize) -> usize {
+    // below this spawning threads costs more than it saves
+    const MIN_PER_THREAD: usize = 4096;
+
+    let available = thread::available_parallelism().map_or(1, |n| n.get());
+    available.min(len / MIN_PER_THREAD).max(1)
+}
+
+/// indices of chunk's entries, offset by start, split by the bucket their key hashes into
+fn partition<K: Hash, V>(
+    chunk: &[FlatMapEntry<K, V>],
+    start: usize,
+    buckets: usize,
+    state: &RandomState,
+) -> Vec<Vec<usize>> {
+    let mut partitioned = vec![Vec::new(); buckets];
+    for (i, entry) in chunk.iter().enumerate() {
+        let bucket = state.hash_one(entry.key()) % buckets as u64;
+        partitioned[bucket as usize].push(start + i);
+    }
+    partitioned
+}
+
+/// first and last index of every key in one bucket, chunks are visited in order so indices ascend
+fn first_and_last_in<'a, K: Hash + Eq, V>(
+    entries: &'a [FlatMapEntry<K, V>],
+    partitioned: &[Vec<Vec<usize>>],
+    bucket: usize,
+) -> Vec<(usize, usize)> {
+    let mut seen: HashMap<&'a K, (usize, usize)> = HashMap::new();
+    for chunk in partitioned {
+        for &i in &chunk[bucket] {
+            seen.entry(entries[i].key())
+                .and_modify(|(_, last)| *last = i)
+                .or_insert((i, i));
+        }
+    }
+    seen.into_values().collect()
+}
+
+/// first and last index of every distinct key, in no particular order
+/// keys are partitioned into one bucket per thread while hashing, so every bucket thread
+/// only visits its own indices, inputs too small to split stay on the calling thread
+fn first_and_last<K: Hash + Eq + Sync, V: Sync>(
+    entries: &[FlatMapEntry<K, V>],
+) -> Vec<(usize, usize)> {
+    let threads = thread_count(entries.len());
+    let state = RandomState::new();
+    if threads == 1 {
+        let partitioned = [partition(entries, 0, 1, &state)];
+        return first_and_last_in(entries, &partitioned, 0);
+    }
+
+    let chunk_len = entries.len().div_ceil(threads);
+    let partitioned: Vec<Vec<Vec<usize>>> = thread::scope(|scope| {
+        let handles: Vec<_> = entries
+            .chunks(chunk_len)
+            .enumerate()
+            .map(|(c, chunk)| {
+                let state = &state;
+                scope.spawn(move || partition(chunk, c * chunk_len, threads, state))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|bucket| {
+                let partitioned = &partitioned;
+                scope.spawn(move || first_and_last_in(entries, partitioned, bucket))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+impl<K: Eq + Hash + Send + Sync, V: Send + Sync> FlatMap<K, V> {
+    /// same semantics as FlatMap::from_entries_hashed, last duplicate stays at the position of its
+    /// last occurrence, but deduplicates across all available threads, for inputs of many thousand entries
+    /// inputs too small to split across threads take FlatMap::from_entries_hashed on the calling thread
+    pub fn par_from_entries(entries: Vec<FlatMapEntry<K, V>>) -> Self {
+        if thread_count(entries.len()) == 1 {
+            return Self::from_entries_hashed(entries.into_iter());
+        }
+
+        let mut entries = entries;
+        let mut keep = vec![false; entries.len()];
+        for (_, last) in first_and_last(&entries) {
+            keep[last] = true;
+        }
+
+        let mut i = 0;
+        entries.retain(|_| {
+            i += 1;
+            keep[i - 1]
+        });
+        crate::diagnostics::grew_to("FlatMap", entries.len());
+        Self::from_inner(entries)
+    }
+
+    /// same semantics as inserting every entry in order, values of existing keys are overwritten in place
+    /// and new keys are appended in the order they first occur, holding their last value
+    pub fn par_extend(&mut self, entries: Vec<FlatMapEntry<K, V>>) {
+        // the entry holding the last value of the key first seen at every index
+        let mut last_of = vec![None; entries.len()];
+        for (first, last) in first_and_last(&entries) {
+            last_of[first] = Some(last);
+        }
+        let mut slots: Vec<_> = entries.into_iter().map(Some).collect();
+        let new: Vec<_> = last_of
+            .into_iter()
+            .flatten()
+            .filter_map(|last| slots[last].take())
+            .collect();
+
+        let index: HashMap<&K, usize> = self
+            .inner
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.key(), i))
+            .collect();
+        let positions: Vec<Option<usize>> = new
+            .iter()
+            .map(|entry| index.get(entry.key()).copied())
+            .collect();
+
+        self.inner
+            .reserve(positions.iter().filter(|p| p.is_none()).count());
+        for (entry, position) in new.into_iter().zip(positions) {
+            match position {
+                Some(i) => *self.inner[i].value_mut() = <(K, V)>::from(entry).1,
+                None => self.push_entry(entry),
+            }
+        }
+    }
+}
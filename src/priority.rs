@@ -0,0 +1,121 @@
+use std::fmt::Debug;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// Linear Map from keys to priorities with peek and pop by best priority
+/// every peek and pop is a scan, which beats a heap at the sizes flat maps are meant for
+pub struct FlatPriorityMap<K: Eq, V: Ord> {
+    inner: FlatMap<K, V>,
+}
+
+impl<K: Eq + Debug, V: Ord + Debug> Debug for FlatPriorityMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatPriorityMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq + Clone, V: Ord + Clone> Clone for FlatPriorityMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Eq, V: Ord> Default for FlatPriorityMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq, V: Ord> FlatPriorityMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: FlatMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.inner.get(k)
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.inner.contains_key(k)
+    }
+
+    /// returns the previous priority of k
+    pub fn insert(&mut self, k: K, priority: V) -> Option<V> {
+        self.inner.insert(k, priority)
+    }
+
+    /// changes the priority of k if present, returns the previous one
+    pub fn update_priority(&mut self, k: &K, priority: V) -> Option<V> {
+        let i = self.inner.get_index_of(k)?;
+        let (_, value) = self.inner.get_index_mut(i)?;
+        Some(std::mem::replace(value, priority))
+    }
+
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        self.inner.delete(k)
+    }
+
+    /// entry with the highest priority, ties go to the entry stored last
+    pub fn peek_max(&self) -> Option<&FlatMapEntry<K, V>> {
+        self.inner.get_index(self.max_index()?)
+    }
+
+    /// entry with the lowest priority, ties go to the entry stored first
+    pub fn peek_min(&self) -> Option<&FlatMapEntry<K, V>> {
+        self.inner.get_index(self.min_index()?)
+    }
+
+    /// removes the entry peek_max returns
+    pub fn pop_max(&mut self) -> Option<(K, V)> {
+        let i = self.max_index()?;
+        Some(self.inner.swap_remove_index(i).into())
+    }
+
+    /// removes the entry peek_min returns
+    pub fn pop_min(&mut self) -> Option<(K, V)> {
+        let i = self.min_index()?;
+        Some(self.inner.swap_remove_index(i).into())
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        let entries = self.inner.as_slice();
+        (0..entries.len()).max_by(|&a, &b| entries[a].value().cmp(entries[b].value()))
+    }
+
+    fn min_index(&self) -> Option<usize> {
+        let entries = self.inner.as_slice();
+        (0..entries.len()).min_by(|&a, &b| entries[a].value().cmp(entries[b].value()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.inner.iter()
+    }
+
+    pub fn into_map(self) -> FlatMap<K, V> {
+        self.inner
+    }
+}
+
+impl<K: Eq, V: Ord> From<FlatMap<K, V>> for FlatPriorityMap<K, V> {
+    fn from(inner: FlatMap<K, V>) -> Self {
+        Self { inner }
+    }
+}
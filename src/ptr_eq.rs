@@ -0,0 +1,65 @@
+//! lookups for interned keys that compare addresses before contents
+//!
+//! a first scan only compares pointers, so a key handed out by an interner is found without
+//! any deep comparison, only if no entry shares its address a second scan compares contents
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::storage::FlatStorage;
+use crate::{FlatMap, FlatMapEntry, FlatSet};
+
+/// keys that can tell they point at the same value without comparing it
+/// equal pointers must imply equal keys, unequal pointers say nothing
+pub trait PtrEq: Eq {
+    fn ptr_eq(&self, other: &Self) -> bool;
+}
+
+impl<T: ?Sized + Eq> PtrEq for &T {
+    fn ptr_eq(&self, other: &Self) -> bool {
+        std::ptr::eq(*self, *other)
+    }
+}
+
+impl<T: ?Sized + Eq> PtrEq for Arc<T> {
+    fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(self, other)
+    }
+}
+
+impl<T: ?Sized + Eq> PtrEq for Rc<T> {
+    fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(self, other)
+    }
+}
+
+fn position_interned<T, K: PtrEq>(items: &[T], key: impl Fn(&T) -> &K, k: &K) -> Option<usize> {
+    items
+        .iter()
+        .position(|item| key(item).ptr_eq(k))
+        .or_else(|| items.iter().position(|item| key(item) == k))
+}
+
+impl<K: PtrEq, V, S: FlatStorage<Item = FlatMapEntry<K, V>>> FlatMap<K, V, S> {
+    /// same as FlatMap::get_index_of, but tries pointer equality on every entry before comparing contents
+    pub fn get_index_of_interned(&self, k: &K) -> Option<usize> {
+        position_interned(self.as_slice(), |entry| entry.key(), k)
+    }
+
+    /// same as FlatMap::get, see FlatMap::get_index_of_interned
+    pub fn get_interned(&self, k: &K) -> Option<&V> {
+        self.get_index_of_interned(k)
+            .map(|i| self.as_slice()[i].value())
+    }
+
+    pub fn contains_key_interned(&self, k: &K) -> bool {
+        self.get_index_of_interned(k).is_some()
+    }
+}
+
+impl<K: PtrEq, S: FlatStorage<Item = K>> FlatSet<K, S> {
+    /// same as FlatSet::has, but tries pointer equality on every item before comparing contents
+    pub fn has_interned(&self, key: &K) -> bool {
+        position_interned(self.as_slice(), |item| item, key).is_some()
+    }
+}
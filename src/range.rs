@@ -0,0 +1,144 @@
+use std::fmt::Debug;
+use std::ops::Range;
+
+/// Linear map from non-overlapping half-open ranges to values, ranges are kept sorted by start
+pub struct FlatRangeMap<K: Ord, V> {
+    inner: Vec<(Range<K>, V)>,
+}
+
+impl<K: Ord + Debug, V: Debug> Debug for FlatRangeMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatRangeMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> Clone for FlatRangeMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for FlatRangeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FlatRangeMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// value of the range containing point
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_range_value(point).map(|(_, v)| v)
+    }
+
+    /// range containing point together with its value
+    pub fn get_range_value(&self, point: &K) -> Option<(&Range<K>, &V)> {
+        for (range, value) in &self.inner {
+            if range.contains(point) {
+                return Some((range, value));
+            }
+        }
+
+        None
+    }
+
+    /// inserts range if it doesn't overlap any existing range
+    /// if it does, returns the range back along with the index of the first overlapping range
+    /// empty ranges are ignored
+    pub fn try_insert(&mut self, range: Range<K>, value: V) -> Result<(), (Range<K>, usize)> {
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        for (i, (existing, _)) in self.inner.iter().enumerate() {
+            if overlaps(existing, &range) {
+                return Err((range, i));
+            }
+        }
+
+        self.insert_sorted(range, value);
+        Ok(())
+    }
+
+    /// inserts range, replacing any overlapping parts of existing ranges
+    /// existing ranges that stick out on either side are split and keep their value
+    /// empty ranges are ignored
+    pub fn insert(&mut self, range: Range<K>, value: V)
+    where
+        K: Clone,
+        V: Clone,
+    {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut i = 0;
+        while i < self.inner.len() {
+            if !overlaps(&self.inner[i].0, &range) {
+                i += 1;
+                continue;
+            }
+
+            let (existing, old) = self.inner.remove(i);
+            if existing.end > range.end {
+                self.inner
+                    .insert(i, (range.end.clone()..existing.end, old.clone()));
+            }
+            if existing.start < range.start {
+                self.inner
+                    .insert(i, (existing.start..range.start.clone(), old));
+                i += 1;
+            }
+        }
+
+        self.insert_sorted(range, value);
+    }
+
+    /// removes the range containing point and returns it with its value
+    pub fn delete(&mut self, point: &K) -> Option<(Range<K>, V)> {
+        let i = self
+            .inner
+            .iter()
+            .position(|(range, _)| range.contains(point))?;
+        Some(self.inner.remove(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// ranges in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = (&Range<K>, &V)> {
+        self.inner.iter().map(|(range, value)| (range, value))
+    }
+
+    fn insert_sorted(&mut self, range: Range<K>, value: V) {
+        let i = self
+            .inner
+            .iter()
+            .position(|(existing, _)| existing.start > range.start)
+            .unwrap_or(self.inner.len());
+        self.inner.insert(i, (range, value));
+    }
+}
+
+fn overlaps<K: Ord>(a: &Range<K>, b: &Range<K>) -> bool {
+    a.start < b.end && b.start < a.end
+}
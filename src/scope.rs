@@ -0,0 +1,132 @@
+//! changes to a FlatMap that are undone when the scope ends, as in lexical scoping
+
+use crate::batch::Undo;
+use crate::storage::GrowableStorage;
+use crate::{FlatMap, FlatMapEntry};
+
+/// records every insert and delete made through it and undoes them on drop,
+/// leaving the map exactly as it was, including entry order, unless commit is called
+/// the shrink policy is held back until the outermost scope commits so undoing never reallocates
+pub struct ScopeGuard<
+    'a,
+    K: Eq,
+    V,
+    S: GrowableStorage<Item = FlatMapEntry<K, V>> = Vec<FlatMapEntry<K, V>>,
+> {
+    map: &'a mut FlatMap<K, V, S>,
+    undo: Vec<Undo<K, V>>,
+    // log of the enclosing scope, which takes over the undo steps on commit
+    parent: Option<&'a mut Vec<Undo<K, V>>>,
+}
+
+impl<K: Eq + std::fmt::Debug, V: std::fmt::Debug, S: GrowableStorage<Item = FlatMapEntry<K, V>>>
+    std::fmt::Debug for ScopeGuard<'_, K, V, S>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScopeGuard")
+            .field("map", &self.map)
+            .field("changes", &self.undo.len())
+            .finish()
+    }
+}
+
+impl<K: Eq, V, S: GrowableStorage<Item = FlatMapEntry<K, V>>> FlatMap<K, V, S> {
+    /// starts a scope whose changes are rolled back when the guard drops
+    pub fn scoped(&mut self) -> ScopeGuard<'_, K, V, S> {
+        self.invalidate_indices();
+        ScopeGuard {
+            map: self,
+            undo: Vec::new(),
+            parent: None,
+        }
+    }
+}
+
+impl<K: Eq, V, S: GrowableStorage<Item = FlatMapEntry<K, V>>> ScopeGuard<'_, K, V, S> {
+    /// nested scope, committing it hands its changes to this one instead of keeping them for good
+    pub fn scoped(&mut self) -> ScopeGuard<'_, K, V, S> {
+        ScopeGuard {
+            map: &mut *self.map,
+            undo: Vec::new(),
+            parent: Some(&mut self.undo),
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.map.get(k)
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.map.contains_key(k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<K, V>> {
+        self.map.iter()
+    }
+
+    pub fn as_map(&self) -> &FlatMap<K, V, S> {
+        self.map
+    }
+
+    /// returns true if k already had a value, which comes back when the scope is undone
+    /// panics if the storage is full
+    pub fn insert(&mut self, k: K, v: V) -> bool {
+        match self.map.get_index_of(&k) {
+            Some(i) => {
+                let old = std::mem::replace(self.map.inner.as_mut_slice()[i].value_mut(), v);
+                self.undo.push(Undo::Restore(i, old));
+                true
+            }
+            None => {
+                if self
+                    .map
+                    .push_for_batch(FlatMapEntry::new(k, v), &mut self.undo)
+                    .is_err()
+                {
+                    panic!("FlatMap storage is full");
+                }
+                false
+            }
+        }
+    }
+
+    /// returns true if k was present, the entry comes back when the scope is undone
+    pub fn delete(&mut self, k: &K) -> bool {
+        match self.map.get_index_of(k) {
+            Some(i) => {
+                self.map.invalidate_indices();
+                let entry = self.map.inner.swap_remove(i);
+                self.undo.push(Undo::Reinsert(i, entry));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// keeps the changes, in a nested scope they are still undone if an enclosing scope drops
+    pub fn commit(mut self) {
+        let undo = std::mem::take(&mut self.undo);
+        match self.parent.take() {
+            Some(parent) => parent.extend(undo),
+            None => self.map.apply_shrink_policy(),
+        }
+    }
+}
+
+impl<K: Eq, V, S: GrowableStorage<Item = FlatMapEntry<K, V>>> Drop for ScopeGuard<'_, K, V, S> {
+    fn drop(&mut self) {
+        if !self.undo.is_empty() {
+            self.map.invalidate_indices();
+            let undo = std::mem::take(&mut self.undo);
+            self.map.rollback(undo);
+        }
+    }
+}
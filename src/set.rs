@@ -1,26 +1,59 @@
-pub struct FlatSet<K: Eq> {
-    inner: Vec<K>,
+use std::marker::PhantomData;
+
+use crate::KeyExists;
+use crate::duplicates::{
+    DuplicateKeyError, find_duplicate, find_duplicate_groups, find_duplicate_ord,
+};
+use crate::map::find_rename;
+use crate::storage::{FlatStorage, FlatStorageMut, GrowableStorage, GrowthStrategy, ShrinkPolicy};
+
+/// storage defaults to a Vec, see FlatStorage for inline and frozen backends
+pub struct FlatSet<K: Eq, S = Vec<K>> {
+    pub(crate) inner: S,
+    shrink: Option<ShrinkPolicy>,
+    growth: GrowthStrategy,
+    marker: PhantomData<K>,
 }
 
-impl<K: Eq + std::fmt::Debug> std::fmt::Debug for FlatSet<K> {
+impl<K: Eq + std::fmt::Debug, S: FlatStorage<Item = K>> std::fmt::Debug for FlatSet<K, S> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FlatSet")
-            .field("inner", &self.inner)
+            .field("inner", &self.inner.as_slice())
             .finish()
     }
 }
 
-impl<K: Eq + Clone> Clone for FlatSet<K> {
+impl<K: Eq, S: Clone> Clone for FlatSet<K, S> {
     fn clone(&self) -> Self {
         Self {
-            inner: self.inner.clone(),
+            shrink: self.shrink,
+            growth: self.growth,
+            ..Self::from_inner(self.inner.clone())
         }
     }
+
+    /// reuses the allocation of self, so repeated snapshots into the same map don't allocate
+    fn clone_from(&mut self, source: &Self) {
+        self.inner.clone_from(&source.inner);
+        self.shrink = source.shrink;
+        self.growth = source.growth;
+    }
 }
 
-impl<K: Eq> Default for FlatSet<K> {
+impl<K: Eq, S: FlatStorage<Item = K> + Default> Default for FlatSet<K, S> {
     fn default() -> Self {
-        Self::new()
+        Self::from_inner(S::default())
+    }
+}
+
+impl<K: Eq, S> FlatSet<K, S> {
+    pub(crate) const fn from_inner(inner: S) -> Self {
+        Self {
+            inner,
+            shrink: None,
+            growth: GrowthStrategy::Amortized,
+            marker: PhantomData,
+        }
     }
 }
 
@@ -31,13 +64,19 @@ impl<K: Eq> FlatSet<K> {
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
-        Self {
-            inner: Vec::with_capacity(capacity),
-        }
+        Self::from_inner(Vec::with_capacity(capacity))
+    }
+
+    /// preallocates capacity and grows by growth once that is used up
+    pub fn with_capacity_and_growth(capacity: usize, growth: GrowthStrategy) -> Self {
+        let mut s = Self::with_capacity(capacity);
+        s.growth = growth;
+        s
     }
 
     /// not recommended for large number of items
     /// use FlatSet::from_iter_unchecked after removing duplicates
+    #[allow(clippy::should_implement_trait)]
     pub fn from_iter(iter: impl Iterator<Item = K>) -> Self {
         let mut s = Self::with_capacity(iter.size_hint().0);
 
@@ -48,53 +87,392 @@ impl<K: Eq> FlatSet<K> {
         s
     }
 
+    /// same semantics as FlatSet::from_iter but allocates exactly once for iter.len() items,
+    /// duplicates leave spare capacity
+    pub fn from_exact_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = K, IntoIter: ExactSizeIterator>,
+    {
+        let iter = iter.into_iter();
+        let mut s = Self::with_capacity(iter.len());
+        for item in iter {
+            s.insert(item);
+        }
+        s
+    }
+
+    /// builds a set from an array literal, duplicates are dropped
+    /// stands in for From<[K; N]>, which would overlap the From<Iterator> impl
+    pub fn from_array<const N: usize>(items: [K; N]) -> Self {
+        Self::from_exact_iter(items)
+    }
+
     /// constructs FlatSet without checking for duplicates
+    /// debug builds still verify the invariant and panic on duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two items are equal
     pub unsafe fn from_iter_unchecked(iter: impl Iterator<Item = K>) -> Self {
-        {
-            Self {
-                inner: iter.collect(),
+        unsafe { Self::from_vec_unchecked(iter.collect()) }
+    }
+
+    /// constructs FlatSet from a vec without checking for duplicates
+    /// debug builds still verify the invariant and panic on duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two items are equal
+    pub unsafe fn from_vec_unchecked(inner: Vec<K>) -> Self {
+        crate::diagnostics::grew_to("FlatSet", inner.len());
+        let s = Self::from_inner(inner);
+        #[cfg(debug_assertions)]
+        s.assert_invariants();
+        s
+    }
+
+    /// constructs FlatSet from items already in strictly ascending order, without sorting or checking in release
+    /// debug builds still verify the order and panic if it doesn't hold
+    ///
+    /// # Safety
+    /// caller must guarantee that every item is greater than the one before it
+    pub unsafe fn from_sorted_vec_unchecked(inner: Vec<K>) -> Self
+    where
+        K: Ord,
+    {
+        #[cfg(debug_assertions)]
+        if let Some(i) = inner.windows(2).position(|w| w[0] >= w[1]) {
+            panic!("FlatSet items are not sorted at index {}", i + 1);
+        }
+        crate::diagnostics::grew_to("FlatSet", inner.len());
+        Self::from_inner(inner)
+    }
+
+    /// constructs FlatSet from items in strictly ascending order, verifying the order as it goes in O(n)
+    /// on failure returns the index of the first item that isn't greater than the previous one
+    pub fn from_sorted_iter(iter: impl Iterator<Item = K>) -> Result<Self, usize>
+    where
+        K: Ord,
+    {
+        let mut inner: Vec<K> = Vec::with_capacity(iter.size_hint().0);
+        for item in iter {
+            if inner.last().is_some_and(|last| *last >= item) {
+                return Err(inner.len());
             }
+            inner.push(item);
+        }
+        Ok(Self::from_inner(inner))
+    }
+
+    pub fn into_vec(self) -> Vec<K> {
+        self.inner
+    }
+
+    /// splits into the items f accepts and the rest in one pass, both keep their order
+    pub fn partition(self, f: impl FnMut(&K) -> bool) -> (Self, Self) {
+        let (accepted, rejected) = self.inner.into_iter().partition(f);
+        // subsets of unique items stay unique
+        (Self::from_inner(accepted), Self::from_inner(rejected))
+    }
+
+    /// keeps only items that are also in other, in place
+    pub fn intersect_with<T: FlatStorage<Item = K>>(&mut self, other: &FlatSet<K, T>) {
+        self.inner.retain(|item| other.has(item));
+        self.apply_shrink_policy();
+    }
+
+    /// removes every item that is in other, in place
+    pub fn difference_with<T: FlatStorage<Item = K>>(&mut self, other: &FlatSet<K, T>) {
+        self.inner.retain(|item| !other.has(item));
+        self.apply_shrink_policy();
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+}
+
+impl<K: Eq, S: FlatStorage<Item = K>> FlatSet<K, S> {
+    /// set over any storage backend, checks for duplicates in O(n^2)
+    /// if found will return the indices of duplicate
+    pub fn from_storage(storage: S) -> Result<Self, DuplicateKeyError> {
+        let s = Self::from_inner(storage);
+        s.check_duplicates()?;
+        Ok(s)
+    }
+
+    /// set over any storage backend without checking for duplicates
+    /// debug builds still verify the invariant and panic on duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two items are equal
+    pub unsafe fn from_storage_unchecked(storage: S) -> Self {
+        let s = Self::from_inner(storage);
+        #[cfg(debug_assertions)]
+        s.assert_invariants();
+        s
+    }
+
+    pub fn into_storage(self) -> S {
+        self.inner
+    }
+
+    /// bytes reserved for items including spare capacity
+    /// heap memory owned by keys or values, e.g. String contents, isn't included
+    pub fn allocated_bytes(&self) -> usize {
+        self.inner.allocated_bytes()
+    }
+
+    /// checks that no two items are equal, if found will return the indices of duplicate
+    pub fn check_duplicates(&self) -> Result<(), DuplicateKeyError> {
+        match find_duplicate(self.as_slice(), |a, b| a == b) {
+            Some(e) => Err(e),
+            None => Ok(()),
         }
     }
 
+    /// full audit after unchecked construction or raw slice edits, O(n²)
+    /// every group lists the indices of equal items, empty if the invariant holds
+    pub fn duplicate_groups(&self) -> Vec<Vec<usize>> {
+        find_duplicate_groups(self.as_slice(), |a, b| a == b)
+    }
+
+    /// panics if two items are equal
+    pub fn assert_invariants(&self) {
+        if let Err(DuplicateKeyError {
+            first: i,
+            second: j,
+        }) = self.check_duplicates()
+        {
+            panic!("FlatSet has duplicate items at indices {i} and {j}");
+        }
+    }
+
+    pub fn as_slice(&self) -> &[K] {
+        self.inner.as_slice()
+    }
+
+    /// slice contains, which core answers with a memchr style scan for byte sized keys
     pub fn has(&self, key: &K) -> bool {
-        for item in self.inner.iter() {
-            if item == key {
-                return true;
+        self.as_slice().contains(key)
+    }
+
+    /// checks every key in a single scan of the storage, results are in the order of keys
+    pub fn has_many(&self, keys: &[&K]) -> Vec<bool> {
+        let mut found = vec![false; keys.len()];
+        let mut remaining = keys.len();
+
+        for item in self.as_slice().iter() {
+            if remaining == 0 {
+                break;
+            }
+            for (i, key) in keys.iter().enumerate() {
+                if !found[i] && item == *key {
+                    found[i] = true;
+                    remaining -= 1;
+                }
             }
         }
 
-        false
+        found
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &K> {
+        self.as_slice().iter()
+    }
+
+    /// items that start with prefix
+    pub fn iter_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a K>
+    where
+        K: AsRef<str>,
+    {
+        self.as_slice()
+            .iter()
+            .filter(move |item| item.as_ref().starts_with(prefix))
+    }
+}
+
+impl<K: Eq, S: FlatStorageMut<Item = K>> FlatSet<K, S> {
+    /// mutable access to the underlying storage, useful for sorting
+    ///
+    /// # Safety
+    /// caller must not introduce equal items through the slice
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [K] {
+        self.inner.as_mut_slice()
     }
 
+    /// replaces the item old with new in place
+    /// returns false if old is missing, replacing an item with itself is a no-op
+    pub fn replace_key(&mut self, old: &K, new: K) -> Result<bool, KeyExists<K>> {
+        match find_rename(self.as_slice(), |k| k, old, &new) {
+            (_, Some(index)) => Err(KeyExists { key: new, index }),
+            (Some(i), None) => {
+                self.inner.as_mut_slice()[i] = new;
+                Ok(true)
+            }
+            (None, None) => Ok(false),
+        }
+    }
+}
+
+impl<K: Eq, S: GrowableStorage<Item = K>> FlatSet<K, S> {
     // returns true if already exists
+    // panics if the storage is full, see FlatSet::try_insert
     pub fn insert(&mut self, key: K) -> bool {
         if self.has(&key) {
             return true;
         }
 
-        self.inner.push(key);
+        self.push_item(key);
         false
     }
 
+    /// same as insert but hands key back instead of panicking when the storage is full
+    pub fn try_insert(&mut self, key: K) -> Result<bool, K> {
+        if self.has(&key) {
+            return Ok(true);
+        }
+
+        self.grow_for_push();
+        self.inner.try_push(key)?;
+        crate::diagnostics::grew_to("FlatSet", self.as_slice().len());
+        Ok(false)
+    }
+
+    /// appends an item known to be missing
+    fn push_item(&mut self, key: K) {
+        self.grow_for_push();
+        if self.inner.try_push(key).is_err() {
+            panic!("FlatSet storage is full");
+        }
+        crate::diagnostics::grew_to("FlatSet", self.as_slice().len());
+    }
+
     // returns true if key exists
     pub fn delete(&mut self, key: &K) -> bool {
-        for i in 0..self.inner.len() {
-            if &self.inner[i] == key {
+        match self.as_slice().iter().position(|item| item == key) {
+            Some(i) => {
                 self.inner.swap_remove(i);
-                return true;
+                self.apply_shrink_policy();
+                true
             }
+            None => false,
         }
+    }
 
-        false
+    /// removes and returns some item, the last one in storage, in O(1)
+    pub fn pop(&mut self) -> Option<K> {
+        let item = self.inner.pop();
+        self.apply_shrink_policy();
+        item
     }
 
-    pub fn shrink_to_fit(&mut self) {
-        self.inner.shrink_to_fit();
+    /// deletes every given key in one backward pass and returns how many were removed
+    pub fn remove_many<'a>(&mut self, keys: impl IntoIterator<Item = &'a K>) -> usize
+    where
+        K: 'a,
+    {
+        let keys: Vec<&K> = keys.into_iter().collect();
+        let mut removed = 0;
+
+        // walking backwards means swap_remove only moves items that were already visited
+        for i in (0..self.as_slice().len()).rev() {
+            if keys.contains(&&self.as_slice()[i]) {
+                self.inner.swap_remove(i);
+                removed += 1;
+            }
+        }
+        self.apply_shrink_policy();
+
+        removed
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &K> {
-        self.inner.iter()
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// opt in to releasing memory automatically after deletes, None turns it off
+    pub fn set_shrink_policy(&mut self, policy: Option<ShrinkPolicy>) {
+        self.shrink = policy;
+        self.apply_shrink_policy();
+    }
+
+    pub fn shrink_policy(&self) -> Option<ShrinkPolicy> {
+        self.shrink
+    }
+
+    /// changes how the storage grows on inserts that find it full
+    pub fn set_growth(&mut self, growth: GrowthStrategy) {
+        self.growth = growth;
+    }
+
+    pub fn growth(&self) -> GrowthStrategy {
+        self.growth
+    }
+
+    fn grow_for_push(&mut self) {
+        let len = self.inner.as_slice().len();
+        if len == self.inner.capacity()
+            && let Some(additional) = self.growth.additional(len)
+        {
+            self.inner.reserve_exact(additional);
+        }
+    }
+
+    fn apply_shrink_policy(&mut self) {
+        if let Some(target) = self
+            .shrink
+            .and_then(|policy| policy.target(self.as_slice().len(), self.inner.capacity()))
+        {
+            self.inner.shrink_to(target);
+        }
+    }
+
+    /// inserts every item that is missing, including repeats within iter only once,
+    /// reserves for the whole batch up front and returns how many items were added
+    /// panics if the storage is full, see FlatSet::try_insert
+    pub fn insert_all(&mut self, iter: impl IntoIterator<Item = K>) -> usize {
+        let iter = iter.into_iter();
+        let before = self.as_slice().len();
+        self.reserve_for_batch(iter.size_hint().0);
+        for item in iter {
+            if !self.has(&item) {
+                self.push_item(item);
+            }
+        }
+        self.as_slice().len() - before
+    }
+
+    /// insert_all for borrowed items, only the ones that are missing get cloned
+    pub fn extend_from_slice(&mut self, items: &[K]) -> usize
+    where
+        K: Clone,
+    {
+        let before = self.as_slice().len();
+        self.reserve_for_batch(items.len());
+        for item in items {
+            if !self.has(item) {
+                self.push_item(item.clone());
+            }
+        }
+        self.as_slice().len() - before
+    }
+
+    fn reserve_for_batch(&mut self, additional: usize) {
+        let spare = self.inner.capacity() - self.as_slice().len();
+        if additional > spare {
+            self.inner.reserve_exact(additional - spare);
+        }
+    }
+
+    /// adds every item of other that is missing from self, in place
+    pub fn union_with<T: FlatStorage<Item = K>>(&mut self, other: &FlatSet<K, T>)
+    where
+        K: Clone,
+    {
+        for item in other.iter() {
+            if !self.has(item) {
+                self.push_item(item.clone());
+            }
+        }
     }
 }
 
@@ -108,6 +486,14 @@ impl<K: Eq> IntoIterator for FlatSet<K> {
     }
 }
 
+impl<K: Eq> FromIterator<K> for FlatSet<K> {
+    /// duplicates are kept once
+    fn from_iter<T: IntoIterator<Item = K>>(iter: T) -> Self {
+        // resolves to the inherent FlatSet::from_iter
+        Self::from_iter(iter.into_iter())
+    }
+}
+
 impl<K: Eq, I> From<I> for FlatSet<K>
 where
     I: Iterator<Item = K>,
@@ -117,8 +503,18 @@ where
     }
 }
 
+impl<K: Eq> Extend<K> for FlatSet<K> {
+    fn extend<T: IntoIterator<Item = K>>(&mut self, iter: T) {
+        let iter = iter.into_iter();
+        self.inner.reserve(iter.size_hint().0);
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
 pub struct ConstantFlatSet<K: Eq, const N: usize> {
-    inner: [K; N],
+    pub(crate) inner: [K; N],
 }
 
 impl<K: Eq + std::fmt::Debug, const N: usize> std::fmt::Debug for ConstantFlatSet<K, N> {
@@ -138,23 +534,19 @@ impl<K: Eq + Clone, const N: usize> Clone for ConstantFlatSet<K, N> {
 }
 
 impl<K: Eq, const N: usize> ConstantFlatSet<K, N> {
+    /// slice contains, which core answers with a memchr style scan for byte sized keys
     pub fn has(&self, key: &K) -> bool {
-        for item in self.inner.iter() {
-            if item == key {
-                return true;
-            }
-        }
-
-        false
+        self.inner.contains(key)
     }
 
     /// checks for duplicates, if found will return the indices of duplicate
     /// not recommended for large list of entries, check for duplicates yourself and use ConstantFlatSet::from_entries_unchecked
-    pub fn from_entries(entries: [K; N]) -> Result<Self, (usize, usize)> {
+    /// or use ConstantFlatSet::from_entries_ord when items are Ord
+    pub fn from_entries(entries: [K; N]) -> Result<Self, DuplicateKeyError> {
         for i in 0..N {
             for j in (i + 1)..N {
                 if entries[i] == entries[j] {
-                    return Err((i, j));
+                    return Err(DuplicateKeyError::new(i, j));
                 }
             }
         }
@@ -162,12 +554,69 @@ impl<K: Eq, const N: usize> ConstantFlatSet<K, N> {
         Ok(unsafe { Self::from_entries_unchecked(entries) })
     }
 
+    /// same as ConstantFlatSet::from_entries, reporting the same indices, but checks in O(N log N)
+    pub fn from_entries_ord(entries: [K; N]) -> Result<Self, DuplicateKeyError>
+    where
+        K: Ord,
+    {
+        if let Some(e) = find_duplicate_ord(&entries, |item| item) {
+            return Err(e);
+        }
+
+        Ok(unsafe { Self::from_entries_unchecked(entries) })
+    }
+
     /// construct Set without checking for duplicates
-    pub unsafe fn from_entries_unchecked(entries: [K; N]) -> Self {
+    /// this is a const fn so unlike FlatSet it can't verify the invariant in debug builds
+    ///
+    /// # Safety
+    /// caller must guarantee that no two items are equal
+    pub const unsafe fn from_entries_unchecked(entries: [K; N]) -> Self {
         Self { inner: entries }
     }
 
+    /// checks that no two items are equal, if found will return the indices of duplicate
+    pub fn check_duplicates(&self) -> Result<(), DuplicateKeyError> {
+        match find_duplicate(&self.inner, |a, b| a == b) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// panics if two items are equal
+    pub fn assert_invariants(&self) {
+        if let Err(DuplicateKeyError {
+            first: i,
+            second: j,
+        }) = self.check_duplicates()
+        {
+            panic!("ConstantFlatSet has duplicate items at indices {i} and {j}");
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    pub fn into_inner(self) -> [K; N] {
+        self.inner
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &K> {
         self.inner.iter()
     }
 }
+
+impl<K: Eq, const N: usize> IntoIterator for ConstantFlatSet<K, N> {
+    type Item = K;
+
+    type IntoIter = std::array::IntoIter<K, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.inner.into_iter()
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt::Debug;
+
+use crate::{FlatMap, FlatMapEntry};
+
+/// Linear Map whose deletes leave a tombstone instead of moving entries,
+/// so indices from get_index_of stay valid until StableFlatMap::compact
+pub struct StableFlatMap<K: Eq, V> {
+    inner: Vec<Option<FlatMapEntry<K, V>>>,
+    len: usize,
+}
+
+impl<K: Eq + Debug, V: Debug> Debug for StableFlatMap<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StableFlatMap")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq + Clone, V: Clone> Clone for StableFlatMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Eq, V> Default for StableFlatMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq, V> StableFlatMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity),
+            len: 0,
+        }
+    }
+
+    pub fn get(&self, k: &K) -> Option<&V> {
+        self.get_index_of(k)
+            .and_then(|i| self.inner[i].as_ref())
+            .map(|entry| entry.value())
+    }
+
+    pub fn get_mut(&mut self, k: &K) -> Option<&mut V> {
+        let i = self.get_index_of(k)?;
+        self.inner[i].as_mut().map(|entry| entry.value_mut())
+    }
+
+    pub fn contains_key(&self, k: &K) -> bool {
+        self.get_index_of(k).is_some()
+    }
+
+    /// index the entry for k is stored at, valid until it is deleted or the map is compacted
+    pub fn get_index_of(&self, k: &K) -> Option<usize> {
+        self.inner
+            .iter()
+            .position(|slot| slot.as_ref().is_some_and(|entry| entry.key() == k))
+    }
+
+    /// entry stored at index, None if the index holds a tombstone
+    pub fn get_index(&self, index: usize) -> Option<&FlatMapEntry<K, V>> {
+        self.inner.get(index)?.as_ref()
+    }
+
+    /// key and mutable value stored at index, None if the index holds a tombstone
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.inner
+            .get_mut(index)?
+            .as_mut()
+            .map(FlatMapEntry::parts_mut)
+    }
+
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.insert_full(k, v).1
+    }
+
+    /// same as insert but also returns the index the entry is stored at
+    /// new entries are appended, tombstones are only reclaimed by compact
+    pub fn insert_full(&mut self, k: K, v: V) -> (usize, Option<V>) {
+        if let Some(i) = self.get_index_of(&k) {
+            let entry = self.inner[i].as_mut().unwrap();
+            return (i, Some(std::mem::replace(entry.value_mut(), v)));
+        }
+
+        self.inner.push(Some(FlatMapEntry::new(k, v)));
+        self.len += 1;
+        crate::diagnostics::grew_to("StableFlatMap", self.inner.len());
+        (self.inner.len() - 1, None)
+    }
+
+    /// leaves a tombstone in place of the entry, other indices don't move
+    pub fn delete(&mut self, k: &K) -> Option<V> {
+        let i = self.get_index_of(k)?;
+        self.len -= 1;
+        self.inner[i].take().map(|entry| <(K, V)>::from(entry).1)
+    }
+
+    /// number of live entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// number of slots left behind by deletes
+    pub fn tombstones(&self) -> usize {
+        self.inner.len() - self.len
+    }
+
+    /// live entries with the index they are stored at
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &FlatMapEntry<K, V>)> {
+        self.inner
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.as_ref().map(|entry| (i, entry)))
+    }
+
+    /// removes tombstones, keeping the order of live entries
+    /// invalidates every index handed out before, see StableFlatMap::compact_with to remap them
+    pub fn compact(&mut self) {
+        self.compact_with(|_, _| {});
+    }
+
+    /// same as compact, calling f with the old and new index of every entry that moved
+    pub fn compact_with(&mut self, mut f: impl FnMut(usize, usize)) {
+        let mut next = 0;
+        for i in 0..self.inner.len() {
+            if self.inner[i].is_some() {
+                if i != next {
+                    self.inner.swap(i, next);
+                    f(i, next);
+                }
+                next += 1;
+            }
+        }
+        self.inner.truncate(next);
+    }
+
+    /// compacts and converts into a FlatMap
+    pub fn into_flat_map(self) -> FlatMap<K, V> {
+        // slots hold distinct keys, so the entries need no second check
+        FlatMap::from_inner(self.inner.into_iter().flatten().collect())
+    }
+}
+
+impl<K: Eq, V> From<FlatMap<K, V>> for StableFlatMap<K, V> {
+    fn from(map: FlatMap<K, V>) -> Self {
+        let len = map.len();
+        Self {
+            inner: map.into_inner().into_iter().map(Some).collect(),
+            len,
+        }
+    }
+}
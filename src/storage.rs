@@ -0,0 +1,424 @@
+//! storage backends FlatMap and FlatSet can be parameterized over
+//!
+//! Vec is the default, InlineStorage keeps up to N items without allocating,
+//! boxed slices, arrays, Arc slices and borrowed slices serve fixed or frozen tables
+
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+use crate::{FlatMap, FlatMapEntry, FlatSet};
+
+/// FlatMap holding up to N entries inline
+pub type InlineFlatMap<K, V, const N: usize> = FlatMap<K, V, InlineStorage<FlatMapEntry<K, V>, N>>;
+
+/// FlatSet holding up to N items inline
+pub type InlineFlatSet<K, const N: usize> = FlatSet<K, InlineStorage<K, N>>;
+
+/// FlatMap on top of a caller provided buffer
+pub type BufferFlatMap<'a, K, V> = FlatMap<K, V, BufferStorage<'a, FlatMapEntry<K, V>>>;
+
+/// FlatSet on top of a caller provided buffer
+pub type BufferFlatSet<'a, K> = FlatSet<K, BufferStorage<'a, K>>;
+
+/// contiguous items the flat collections scan
+pub trait FlatStorage {
+    type Item;
+
+    fn as_slice(&self) -> &[Self::Item];
+
+    /// bytes reserved for items including spare capacity, not counting heap memory owned by the items
+    fn allocated_bytes(&self) -> usize {
+        size_of_val(self.as_slice())
+    }
+}
+
+/// storage whose items can be modified in place
+pub trait FlatStorageMut: FlatStorage {
+    fn as_mut_slice(&mut self) -> &mut [Self::Item];
+}
+
+/// storage that can add and remove items
+pub trait GrowableStorage: FlatStorageMut {
+    /// appends item, handing it back if the storage is at capacity
+    fn try_push(&mut self, item: Self::Item) -> Result<(), Self::Item>;
+
+    /// removes the item at index by moving the last item into its place
+    /// panics if index is out of bounds
+    fn swap_remove(&mut self, index: usize) -> Self::Item;
+
+    fn pop(&mut self) -> Option<Self::Item>;
+
+    /// how many items fit without reallocating, if the storage can't grow this is its fixed size
+    fn capacity(&self) -> usize;
+
+    /// releases spare capacity down to at least min_capacity, no-op for fixed storage
+    fn shrink_to(&mut self, _min_capacity: usize) {}
+
+    /// makes room for exactly additional more items, no-op for fixed storage
+    fn reserve_exact(&mut self, _additional: usize) {}
+}
+
+/// how the backing storage grows when an insert finds it full
+#[derive(Debug, Clone, Copy, Default)]
+pub enum GrowthStrategy {
+    /// the storage's own amortized growth, doubling for Vec
+    #[default]
+    Amortized,
+    /// one slot at a time, for maps that are known to stay small
+    Exact,
+    /// maps the current len to the new capacity, values not above len grow by one
+    Custom(fn(usize) -> usize),
+}
+
+impl GrowthStrategy {
+    /// extra slots to reserve before pushing into full storage, None leaves it to the storage
+    pub(crate) fn additional(&self, len: usize) -> Option<usize> {
+        match self {
+            Self::Amortized => None,
+            Self::Exact => Some(1),
+            Self::Custom(f) => Some(f(len).saturating_sub(len).max(1)),
+        }
+    }
+}
+
+/// shrinks the backing storage once deletes leave len below capacity / divisor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkPolicy {
+    divisor: usize,
+    min_capacity: usize,
+}
+
+impl ShrinkPolicy {
+    /// capacity is never shrunk below min_capacity
+    ///
+    /// # Panics
+    ///
+    /// if divisor is below 2, with 0 or 1 storage could shrink on every delete and regrow on the next insert
+    /// use ShrinkPolicy::try_new for divisors that aren't known up front
+    pub const fn new(divisor: usize, min_capacity: usize) -> Self {
+        match Self::try_new(divisor, min_capacity) {
+            Some(policy) => policy,
+            None => panic!("ShrinkPolicy divisor must be at least 2"),
+        }
+    }
+
+    /// same as ShrinkPolicy::new but returns None instead of panicking if divisor is below 2
+    pub const fn try_new(divisor: usize, min_capacity: usize) -> Option<Self> {
+        if divisor < 2 {
+            return None;
+        }
+        Some(Self {
+            divisor,
+            min_capacity,
+        })
+    }
+
+    pub const fn divisor(&self) -> usize {
+        self.divisor
+    }
+
+    pub const fn min_capacity(&self) -> usize {
+        self.min_capacity
+    }
+
+    /// capacity to shrink to, if any
+    pub(crate) fn target(&self, len: usize, capacity: usize) -> Option<usize> {
+        let shrink = capacity > self.min_capacity && len.saturating_mul(self.divisor) < capacity;
+        shrink.then(|| len.max(self.min_capacity))
+    }
+}
+
+impl Default for ShrinkPolicy {
+    /// shrinks when less than a quarter is used, keeps at least 16 slots
+    fn default() -> Self {
+        Self::new(4, 16)
+    }
+}
+
+impl<T> FlatStorage for Vec<T> {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        self.capacity() * size_of::<T>()
+    }
+}
+
+impl<T> FlatStorageMut for Vec<T> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T> GrowableStorage for Vec<T> {
+    fn try_push(&mut self, item: T) -> Result<(), T> {
+        self.push(item);
+        Ok(())
+    }
+
+    fn swap_remove(&mut self, index: usize) -> T {
+        Vec::swap_remove(self, index)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        Vec::pop(self)
+    }
+
+    fn capacity(&self) -> usize {
+        Vec::capacity(self)
+    }
+
+    fn shrink_to(&mut self, min_capacity: usize) {
+        Vec::shrink_to(self, min_capacity);
+    }
+
+    fn reserve_exact(&mut self, additional: usize) {
+        Vec::reserve_exact(self, additional);
+    }
+}
+
+impl<T> FlatStorage for Box<[T]> {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T> FlatStorageMut for Box<[T]> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, const N: usize> FlatStorage for [T; N] {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, const N: usize> FlatStorageMut for [T; N] {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T> FlatStorage for Arc<[T]> {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T> FlatStorage for &[T] {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+}
+
+/// fixed capacity storage kept inline, e.g. on the stack, no allocation
+pub struct InlineStorage<T, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> InlineStorage<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [const { MaybeUninit::uninit() }; N],
+            len: 0,
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+impl<T, const N: usize> Default for InlineStorage<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for InlineStorage<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for InlineStorage<T, N> {
+    fn clone(&self) -> Self {
+        let mut s = Self::new();
+        for item in self.as_slice() {
+            // same capacity as self, can't be full
+            let _ = s.try_push(item.clone());
+        }
+        s
+    }
+}
+
+impl<T, const N: usize> Drop for InlineStorage<T, N> {
+    fn drop(&mut self) {
+        // the first len items are initialized and dropped exactly once
+        unsafe { std::ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+impl<T, const N: usize> FlatStorage for InlineStorage<T, N> {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        // the first len items are initialized
+        unsafe { std::slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        size_of_val(&self.buf)
+    }
+}
+
+impl<T, const N: usize> FlatStorageMut for InlineStorage<T, N> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // the first len items are initialized
+        unsafe { std::slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<T, const N: usize> GrowableStorage for InlineStorage<T, N> {
+    fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(item);
+        }
+        self.buf[self.len].write(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index {index} out of bounds");
+        let last = self.len - 1;
+        self.as_mut_slice().swap(index, last);
+        self.pop().unwrap()
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // the item at len was initialized and is no longer counted, so it is read once
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    fn capacity(&self) -> usize {
+        N
+    }
+}
+
+/// storage inside a caller provided buffer, e.g. a stack array, a static or an arena slice
+/// capacity is the buffer length, items still in the buffer are dropped with the storage
+pub struct BufferStorage<'a, T> {
+    buf: &'a mut [MaybeUninit<T>],
+    len: usize,
+}
+
+impl<'a, T> BufferStorage<'a, T> {
+    /// empty storage, existing contents of buf are ignored
+    pub const fn new(buf: &'a mut [MaybeUninit<T>]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl<T: Debug> Debug for BufferStorage<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.as_slice()).finish()
+    }
+}
+
+impl<T> Drop for BufferStorage<'_, T> {
+    fn drop(&mut self) {
+        // the first len items are initialized and dropped exactly once
+        unsafe { std::ptr::drop_in_place(self.as_mut_slice()) }
+    }
+}
+
+impl<T> FlatStorage for BufferStorage<'_, T> {
+    type Item = T;
+
+    fn as_slice(&self) -> &[T] {
+        // the first len items are initialized
+        unsafe { std::slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        size_of_val(self.buf)
+    }
+}
+
+impl<T> FlatStorageMut for BufferStorage<'_, T> {
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // the first len items are initialized
+        unsafe { std::slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast(), self.len) }
+    }
+}
+
+impl<T> GrowableStorage for BufferStorage<'_, T> {
+    fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.len == self.buf.len() {
+            return Err(item);
+        }
+        self.buf[self.len].write(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn swap_remove(&mut self, index: usize) -> T {
+        assert!(index < self.len, "index {index} out of bounds");
+        let last = self.len - 1;
+        self.as_mut_slice().swap(index, last);
+        self.pop().unwrap()
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        // the item at len was initialized and is no longer counted, so it is read once
+        Some(unsafe { self.buf[self.len].assume_init_read() })
+    }
+
+    fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+}
+
+impl<'a, K: Eq, V> BufferFlatMap<'a, K, V> {
+    /// empty map that stores its entries in buf and never allocates
+    /// use FlatMap::try_insert to handle a full buffer
+    pub const fn from_buffer(buf: &'a mut [MaybeUninit<FlatMapEntry<K, V>>]) -> Self {
+        Self::from_inner(BufferStorage::new(buf))
+    }
+}
+
+impl<'a, K: Eq> BufferFlatSet<'a, K> {
+    /// empty set that stores its items in buf and never allocates
+    /// use FlatSet::try_insert to handle a full buffer
+    pub const fn from_buffer(buf: &'a mut [MaybeUninit<K>]) -> Self {
+        Self::from_inner(BufferStorage::new(buf))
+    }
+}
@@ -0,0 +1,158 @@
+//! traits shared by the map and set variants so code can be generic over "some flat map",
+//! e.g. accept a ConstantFlatMap table and a runtime FlatMap through the same function
+//!
+//! every read variant is a slice of unique entries, so implementors only provide as_slice
+
+use crate::map::find_index;
+use crate::storage::{FlatStorage, GrowableStorage};
+use crate::{
+    ConstantFlatMap, ConstantFlatSet, FlatMap, FlatMapEntry, FlatMapView, FlatSet, FlatSetView,
+    FrozenFlatMap,
+};
+
+pub trait FlatMapRead {
+    type Key: Eq;
+    type Value;
+
+    /// entries in storage order, keys are unique
+    fn as_slice(&self) -> &[FlatMapEntry<Self::Key, Self::Value>];
+
+    fn get(&self, k: &Self::Key) -> Option<&Self::Value> {
+        let entries = self.as_slice();
+        find_index(entries, k).map(|i| entries[i].value())
+    }
+
+    fn contains_key(&self, k: &Self::Key) -> bool {
+        find_index(self.as_slice(), k).is_some()
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &FlatMapEntry<Self::Key, Self::Value>> {
+        self.as_slice().iter()
+    }
+}
+
+pub trait FlatMapWrite: FlatMapRead {
+    /// returns the previous value of k
+    fn insert(&mut self, k: Self::Key, v: Self::Value) -> Option<Self::Value>;
+
+    fn delete(&mut self, k: &Self::Key) -> Option<Self::Value>;
+}
+
+pub trait FlatSetRead {
+    type Key: Eq;
+
+    /// items in storage order, unique
+    fn as_slice(&self) -> &[Self::Key];
+
+    fn has(&self, key: &Self::Key) -> bool {
+        self.as_slice().contains(key)
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &Self::Key> {
+        self.as_slice().iter()
+    }
+}
+
+pub trait FlatSetWrite: FlatSetRead {
+    /// returns true if key already exists
+    fn insert(&mut self, key: Self::Key) -> bool;
+
+    /// returns true if key existed
+    fn delete(&mut self, key: &Self::Key) -> bool;
+}
+
+impl<K: Eq, V, S: FlatStorage<Item = FlatMapEntry<K, V>>> FlatMapRead for FlatMap<K, V, S> {
+    type Key = K;
+    type Value = V;
+
+    fn as_slice(&self) -> &[FlatMapEntry<K, V>] {
+        FlatMap::as_slice(self)
+    }
+}
+
+impl<K: Eq, V, S: GrowableStorage<Item = FlatMapEntry<K, V>>> FlatMapWrite for FlatMap<K, V, S> {
+    fn insert(&mut self, k: K, v: V) -> Option<V> {
+        FlatMap::insert(self, k, v)
+    }
+
+    fn delete(&mut self, k: &K) -> Option<V> {
+        FlatMap::delete(self, k)
+    }
+}
+
+impl<K: Eq, V, const N: usize> FlatMapRead for ConstantFlatMap<K, V, N> {
+    type Key = K;
+    type Value = V;
+
+    fn as_slice(&self) -> &[FlatMapEntry<K, V>] {
+        &self.inner
+    }
+}
+
+impl<K: Eq, V> FlatMapRead for FrozenFlatMap<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn as_slice(&self) -> &[FlatMapEntry<K, V>] {
+        FrozenFlatMap::as_slice(self)
+    }
+}
+
+impl<K: Eq, V> FlatMapRead for FlatMapView<'_, K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn as_slice(&self) -> &[FlatMapEntry<K, V>] {
+        FlatMapView::as_slice(self)
+    }
+}
+
+impl<K: Eq, S: FlatStorage<Item = K>> FlatSetRead for FlatSet<K, S> {
+    type Key = K;
+
+    fn as_slice(&self) -> &[K] {
+        FlatSet::as_slice(self)
+    }
+}
+
+impl<K: Eq, S: GrowableStorage<Item = K>> FlatSetWrite for FlatSet<K, S> {
+    fn insert(&mut self, key: K) -> bool {
+        FlatSet::insert(self, key)
+    }
+
+    fn delete(&mut self, key: &K) -> bool {
+        FlatSet::delete(self, key)
+    }
+}
+
+impl<K: Eq, const N: usize> FlatSetRead for ConstantFlatSet<K, N> {
+    type Key = K;
+
+    fn as_slice(&self) -> &[K] {
+        &self.inner
+    }
+}
+
+impl<K: Eq> FlatSetRead for FlatSetView<'_, K> {
+    type Key = K;
+
+    fn as_slice(&self) -> &[K] {
+        FlatSetView::as_slice(self)
+    }
+}
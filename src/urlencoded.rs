@@ -0,0 +1,86 @@
+//! application/x-www-form-urlencoded parsing and writing, as used by query strings and HTML forms
+
+use crate::FlatMultiMap;
+
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// '+' becomes a space and %XX the byte it encodes, malformed escapes are kept as they are
+fn decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => {
+                let hi = bytes.get(i + 1).copied().and_then(hex_value);
+                let lo = bytes.get(i + 2).copied().and_then(hex_value);
+                match (hi, lo) {
+                    (Some(hi), Some(lo)) => {
+                        out.push(hi << 4 | lo);
+                        i += 2;
+                    }
+                    _ => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// leaves alphanumerics and *-._ as they are, spaces become '+' and every other byte %XX
+fn encode_into(out: &mut String, s: &str) {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                out.push(b as char)
+            }
+            b' ' => out.push('+'),
+            b => {
+                out.push('%');
+                out.push(HEX[(b >> 4) as usize] as char);
+                out.push(HEX[(b & 0xf) as usize] as char);
+            }
+        }
+    }
+}
+
+impl FlatMultiMap<String, String> {
+    /// parses a query string or form body, a leading '?' is skipped
+    /// repeated keys keep every value in order, a pair without '=' gets an empty value
+    /// invalid UTF-8 after decoding is replaced with U+FFFD
+    pub fn from_urlencoded(s: &str) -> Self {
+        let s = s.strip_prefix('?').unwrap_or(s);
+        let mut map = Self::new();
+        for pair in s.split('&').filter(|pair| !pair.is_empty()) {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            map.append(decode(k), decode(v));
+        }
+        map
+    }
+
+    /// writes entries in insertion order as key=value pairs joined by '&'
+    pub fn to_urlencoded(&self) -> String {
+        let mut out = String::new();
+        for (i, entry) in self.iter().enumerate() {
+            if i > 0 {
+                out.push('&');
+            }
+            encode_into(&mut out, entry.key());
+            out.push('=');
+            encode_into(&mut out, entry.value());
+        }
+        out
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::Debug;
+
+use crate::map::find_index;
+use crate::{DuplicateKeyError, FlatMapEntry};
+
+/// Borrowed map over a slice of entries, provides lookups without owning the data
+pub struct FlatMapView<'a, K: Eq, V> {
+    inner: &'a [FlatMapEntry<K, V>],
+}
+
+impl<K: Eq + Debug, V: Debug> Debug for FlatMapView<'_, K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatMapView")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq, V> Clone for FlatMapView<'_, K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K: Eq, V> Copy for FlatMapView<'_, K, V> {}
+
+impl<'a, K: Eq, V> FlatMapView<'a, K, V> {
+    /// checks for duplicates, if found will return the indices of duplicate
+    /// not recommended for large slices, check for duplicates yourself and use FlatMapView::from_slice_unchecked
+    pub fn from_slice(entries: &'a [FlatMapEntry<K, V>]) -> Result<Self, DuplicateKeyError> {
+        for i in 0..entries.len() {
+            for j in (i + 1)..entries.len() {
+                if entries[i].key() == entries[j].key() {
+                    return Err(DuplicateKeyError::new(i, j));
+                }
+            }
+        }
+
+        Ok(unsafe { Self::from_slice_unchecked(entries) })
+    }
+
+    /// construct view without checking for duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two entries have equal keys
+    pub const unsafe fn from_slice_unchecked(entries: &'a [FlatMapEntry<K, V>]) -> Self {
+        Self { inner: entries }
+    }
+
+    /// view over a table owned elsewhere, e.g. handed over from C
+    ///
+    /// # Safety
+    /// ptr must be valid for reads of len entries for 'a, see std::slice::from_raw_parts,
+    /// and caller must guarantee that no two entries have equal keys
+    pub unsafe fn from_raw_parts(ptr: *const FlatMapEntry<K, V>, len: usize) -> Self {
+        unsafe { Self::from_slice_unchecked(std::slice::from_raw_parts(ptr, len)) }
+    }
+
+    /// reinterpret bytes (from include_bytes! or an mmap) as a table of entries without copying
+    /// returns None if bytes are misaligned or not a whole number of entries
+    ///
+    /// # Safety
+    /// K and V must be plain data types valid for any bit pattern with no padding in FlatMapEntry<K, V>,
+    /// and caller must guarantee that no two entries have equal keys
+    pub unsafe fn from_bytes_unchecked(bytes: &'a [u8]) -> Option<Self> {
+        let entries = unsafe { cast_slice::<FlatMapEntry<K, V>>(bytes)? };
+        Some(unsafe { Self::from_slice_unchecked(entries) })
+    }
+
+    pub fn get(&self, k: &K) -> Option<&'a V> {
+        find_index(self.inner, k).map(|i| self.inner[i].value())
+    }
+
+    pub const fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub const fn as_slice(&self) -> &'a [FlatMapEntry<K, V>] {
+        self.inner
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a FlatMapEntry<K, V>> + use<'a, K, V> {
+        self.inner.iter()
+    }
+}
+
+/// Borrowed set over a slice of items, provides lookups without owning the data
+pub struct FlatSetView<'a, K: Eq> {
+    inner: &'a [K],
+}
+
+impl<K: Eq + Debug> Debug for FlatSetView<'_, K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlatSetView")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<K: Eq> Clone for FlatSetView<'_, K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K: Eq> Copy for FlatSetView<'_, K> {}
+
+impl<'a, K: Eq> FlatSetView<'a, K> {
+    /// checks for duplicates, if found will return the indices of duplicate
+    /// not recommended for large slices, check for duplicates yourself and use FlatSetView::from_slice_unchecked
+    pub fn from_slice(items: &'a [K]) -> Result<Self, DuplicateKeyError> {
+        for i in 0..items.len() {
+            for j in (i + 1)..items.len() {
+                if items[i] == items[j] {
+                    return Err(DuplicateKeyError::new(i, j));
+                }
+            }
+        }
+
+        Ok(unsafe { Self::from_slice_unchecked(items) })
+    }
+
+    /// construct view without checking for duplicates
+    ///
+    /// # Safety
+    /// caller must guarantee that no two items are equal
+    pub const unsafe fn from_slice_unchecked(items: &'a [K]) -> Self {
+        Self { inner: items }
+    }
+
+    /// reinterpret bytes (from include_bytes! or an mmap) as a table of items without copying
+    /// returns None if bytes are misaligned or not a whole number of items
+    ///
+    /// # Safety
+    /// K must be a plain data type valid for any bit pattern with no padding,
+    /// and caller must guarantee that no two items are equal
+    pub unsafe fn from_bytes_unchecked(bytes: &'a [u8]) -> Option<Self> {
+        let items = unsafe { cast_slice::<K>(bytes)? };
+        Some(unsafe { Self::from_slice_unchecked(items) })
+    }
+
+    /// slice contains, which core answers with a memchr style scan for byte sized keys
+    pub fn has(&self, key: &K) -> bool {
+        self.inner.contains(key)
+    }
+
+    pub const fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    pub const fn as_slice(&self) -> &'a [K] {
+        self.inner
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a K> + use<'a, K> {
+        self.inner.iter()
+    }
+}
+
+/// # Safety
+/// T must be valid for any bit pattern
+unsafe fn cast_slice<T>(bytes: &[u8]) -> Option<&[T]> {
+    let size = std::mem::size_of::<T>();
+    if size == 0 || !bytes.len().is_multiple_of(size) || !bytes.as_ptr().cast::<T>().is_aligned() {
+        return None;
+    }
+
+    Some(unsafe { std::slice::from_raw_parts(bytes.as_ptr().cast::<T>(), bytes.len() / size) })
+}
@@ -0,0 +1,6 @@
+pub static COUNTRIES: ::flatmap::ConstantFlatMap<&'static str, &'static str, 2> = unsafe {
+    ::flatmap::ConstantFlatMap::from_entries_unchecked([
+        ::flatmap::FlatMapEntry::new("de", "Germany"),
+        ::flatmap::FlatMapEntry::new("fr", "France, \"Republic\""),
+    ])
+};
@@ -0,0 +1,7 @@
+pub static IDS: ::std::sync::LazyLock<::flatmap::ConstantFlatMap<u32, char, 2>> = ::std::sync::LazyLock::new(|| {
+    ::flatmap::ConstantFlatMap::from_entries([
+        ::flatmap::FlatMapEntry::new(1, 'a'),
+        ::flatmap::FlatMapEntry::new(0x2, 'b'),
+    ])
+    .expect("duplicate keys in IDS")
+});
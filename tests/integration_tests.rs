@@ -1,8 +1,12 @@
-use flatmap::{ConstantFlatMap, ConstantFlatSet, FlatMap, FlatMapEntry, FlatSet};
+use flatmap::{
+    ConstantFlatMap, ConstantFlatSet, DuplicateKeyError, FlatMap, FlatMapEntry, FlatMapView,
+    FlatSet, FlatSetView, FrozenFlatMap, HybridMap,
+};
 
 #[cfg(test)]
 mod flatmap_tests {
     use super::*;
+    use flatmap::NotSortedError;
 
     #[test]
     fn test_flatmap_entry() {
@@ -28,11 +32,41 @@ mod flatmap_tests {
         assert_eq!(map.insert("key", 100), Some(42));
         assert_eq!(map.get(&"key"), Some(&100));
         
+        assert!(map.contains_key(&"key"));
+
         assert_eq!(map.delete(&"key"), Some(100));
         assert_eq!(map.get(&"key"), None);
+        assert!(!map.contains_key(&"key"));
         assert_eq!(map.delete(&"nonexistent"), None);
     }
 
+    #[test]
+    fn test_flatmap_get_key_value() {
+        // only name takes part in Eq, id is metadata of the stored instance
+        #[derive(Debug)]
+        struct Tag {
+            name: &'static str,
+            id: u32,
+        }
+
+        impl PartialEq for Tag {
+            fn eq(&self, other: &Self) -> bool {
+                self.name == other.name
+            }
+        }
+
+        impl Eq for Tag {}
+
+        let mut map = FlatMap::new();
+        map.insert(Tag { name: "db", id: 7 }, "postgres");
+
+        let probe = Tag { name: "db", id: 0 };
+        assert_eq!(map.get_key(&probe).map(|tag| tag.id), Some(7));
+        let (key, value) = map.get_key_value(&probe).unwrap();
+        assert_eq!((key.id, *value), (7, "postgres"));
+        assert!(map.get_key_value(&Tag { name: "cache", id: 7 }).is_none());
+    }
+
     #[test]
     fn test_flatmap_from_entries() {
         let entries = vec![
@@ -46,6 +80,105 @@ mod flatmap_tests {
         assert_eq!(map.get(&"b"), Some(&2));
     }
 
+    #[test]
+    fn test_flatmap_from_exact_iter() {
+        let entries: Vec<_> = (0..100).map(|i| FlatMapEntry::new(i % 50, i)).collect();
+        let map = FlatMap::from_exact_iter(entries);
+        assert_eq!(map.len(), 50);
+        assert_eq!(map.capacity(), 100);
+        assert_eq!(map.get(&0), Some(&50));
+
+        let set = FlatSet::from_exact_iter([3, 1, 3]);
+        assert_eq!(set.capacity(), 3);
+        assert!(set.has(&1));
+    }
+
+    #[test]
+    fn test_from_array() {
+        let map = FlatMap::from_array([("a", 1), ("b", 2), ("a", 3)]);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"a"), Some(&3)); // last value wins
+        assert_eq!(map.get(&"b"), Some(&2));
+
+        let set = FlatSet::from_array([3, 1, 3, 2, 1]);
+        assert_eq!(set.iter().count(), 3);
+        assert!(set.has(&1) && set.has(&2) && set.has(&3));
+
+        let empty: FlatMap<u8, u8> = FlatMap::from_array([]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_flatmap_from_entries_dedup_last() {
+        let entries = vec![
+            FlatMapEntry::new("b", 1),
+            FlatMapEntry::new("a", 2),
+            FlatMapEntry::new("b", 3),
+            FlatMapEntry::new("c", 4),
+            FlatMapEntry::new("a", 5),
+        ];
+
+        let map = FlatMap::from_entries_dedup_last(entries.into_iter());
+        assert_eq!(map.iter().count(), 3);
+        assert_eq!(map.get(&"a"), Some(&5));
+        assert_eq!(map.get(&"b"), Some(&3));
+        assert_eq!(map.get(&"c"), Some(&4));
+
+        let keys: Vec<_> = map.iter().map(|entry| *entry.key()).collect();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_flatmap_from_entries_hashed() {
+        let entries = (0..5000).map(|i| FlatMapEntry::new(i % 1000, i));
+        let map = FlatMap::from_entries_hashed(entries);
+
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.get(&0), Some(&4000));
+        assert_eq!(map.get(&999), Some(&4999));
+        map.assert_invariants();
+    }
+
+    #[test]
+    fn test_flatmap_from_sorted() {
+        let map = FlatMap::from_sorted_iter((0..100).map(|i| FlatMapEntry::new(i, i * 2))).unwrap();
+        assert_eq!(map.get(&42), Some(&84));
+        map.assert_invariants();
+
+        let unsorted = [1, 2, 2, 3].into_iter().map(|i| FlatMapEntry::new(i, ()));
+        assert_eq!(FlatMap::from_sorted_iter(unsorted).unwrap_err(), 2);
+
+        let entries = vec![FlatMapEntry::new("a", 1), FlatMapEntry::new("b", 2)];
+        let map = unsafe { FlatMap::from_sorted_vec_unchecked(entries) };
+        assert_eq!(map.get(&"b"), Some(&2));
+    }
+
+    #[test]
+    fn test_flatmap_merge_sorted() {
+        let shard = |keys: &[i32]| FlatMap::from_sorted_iter(keys.iter().map(|&k| FlatMapEntry::new(k, 1))).unwrap();
+        let shards = vec![shard(&[1, 4, 7]), shard(&[2, 4]), shard(&[]), shard(&[4, 9])];
+
+        let merged = FlatMap::merge_sorted(shards, |_, a, b| a + b).unwrap();
+        let keys: Vec<_> = merged.iter().map(|e| *e.key()).collect();
+        assert_eq!(keys, vec![1, 2, 4, 7, 9]);
+        assert_eq!(merged.get(&4), Some(&3));
+        merged.assert_invariants();
+    }
+
+    #[test]
+    fn test_flatmap_merge_sorted_rejects_unsorted() {
+        let sorted = FlatMap::from_entries([(1, ()), (2, ())].into_iter().map(FlatMapEntry::from));
+        let mut unsorted = FlatMap::new();
+        unsorted.insert(2, ());
+        unsorted.insert(5, ());
+        unsorted.insert(1, ());
+        // the unsorted input comes last, so a merge that checked lazily would have called conflict for key 2
+        let err = FlatMap::merge_sorted([sorted, unsorted], |_, _, _| panic!("merged before checking"))
+            .unwrap_err();
+        assert_eq!(err, NotSortedError { source: 1, position: 2 });
+        assert_eq!(err.to_string(), "input 1 is not in ascending key order at index 2");
+    }
+
     #[test]
     fn test_flatmap_from_tuples() {
         let tuples = vec![("x", 10), ("y", 20)];
@@ -55,6 +188,420 @@ mod flatmap_tests {
         assert_eq!(map.get(&"y"), Some(&20));
     }
 
+    #[test]
+    fn test_flatmap_from_array_iter() {
+        let map: FlatMap<_, _> = [("a", 1), ("b", 2), ("a", 3)].into_iter().collect();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"a"), Some(&3));
+    }
+
+    #[test]
+    fn test_flatmap_get_many() {
+        let mut map = FlatMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        map.insert("c", 3);
+
+        let found = map.get_many(&[&"c", &"x", &"a", &"c"]);
+        assert_eq!(found, vec![Some(&3), None, Some(&1), Some(&3)]);
+        assert!(map.get_many(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_flatmap_remove_many() {
+        let mut map = FlatMap::new();
+        for (i, key) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            map.insert(key, i);
+        }
+
+        let mut removed = map.remove_many([&"a", &"e", &"c", &"x"]);
+        removed.sort();
+        assert_eq!(removed, vec![0, 2, 4]);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"b"), Some(&1));
+        assert_eq!(map.get(&"d"), Some(&3));
+    }
+
+    #[test]
+    fn test_flatmap_extend() {
+        let mut map = FlatMap::new();
+        map.insert("a", 1);
+        map.extend([("a", 10), ("b", 2)]);
+        map.extend(vec![FlatMapEntry::new("c", 3)]);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&"a"), Some(&10));
+        assert_eq!(map.get(&"c"), Some(&3));
+    }
+
+    #[test]
+    fn test_flatmap_entry_ref() {
+        let mut counts: FlatMap<String, u32> = FlatMap::new();
+        for word in ["a", "b", "a"] {
+            *counts.entry_ref(word).or_insert(0) += 1;
+        }
+        assert_eq!(counts.get(&"a".to_string()), Some(&2));
+        assert_eq!(counts.get(&"b".to_string()), Some(&1));
+
+        counts.entry_ref("b").and_modify(|count| *count = 10).or_default();
+        assert_eq!(counts.get(&"b".to_string()), Some(&10));
+
+        match counts.entry_ref("c") {
+            flatmap::EntryRef::Vacant(entry) => assert_eq!(entry.key(), "c"),
+            flatmap::EntryRef::Occupied(_) => panic!("c was never inserted"),
+        }
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn test_flatmap_get_or_insert_with() {
+        let mut memo = FlatMap::new();
+        let mut calls = 0;
+
+        for _ in 0..3 {
+            let value = memo.get_or_insert_with(4, || {
+                calls += 1;
+                16
+            });
+            *value += 1;
+        }
+
+        assert_eq!(calls, 1);
+        assert_eq!(memo.get(&4), Some(&19));
+    }
+
+    #[test]
+    fn test_flatmap_update_upsert() {
+        let mut map = FlatMap::new();
+        assert!(!map.update(&"hits", |hits| *hits += 1));
+
+        map.upsert("hits", || 1, |hits| *hits += 1);
+        map.upsert("hits", || 1, |hits| *hits += 1);
+        assert_eq!(map.get(&"hits"), Some(&2));
+
+        assert!(map.update(&"hits", |hits| *hits *= 10));
+        assert_eq!(map.get(&"hits"), Some(&20));
+    }
+
+    #[test]
+    fn test_flatmap_insert_full() {
+        let mut map = FlatMap::new();
+        assert_eq!(map.insert_full("a", 1), (0, None));
+        assert_eq!(map.insert_full("b", 2), (1, None));
+        assert_eq!(map.insert_full("a", 3), (0, Some(1)));
+
+        assert_eq!(map.get_index(1).unwrap().key(), &"b");
+        assert!(map.get_index(2).is_none());
+        assert_eq!(map.get_index_of(&"b"), Some(1));
+        assert_eq!(map.get_index_of(&"c"), None);
+
+        let (key, value) = map.get_index_mut(0).unwrap();
+        assert_eq!(*key, "a");
+        *value = 10;
+        assert_eq!(map.get(&"a"), Some(&10));
+    }
+
+    #[test]
+    fn test_flatmap_replace_key() {
+        let mut map = FlatMap::from_entries(
+            vec![FlatMapEntry::new("a", 1), FlatMapEntry::new("b", 2)].into_iter(),
+        );
+        assert!(map.replace_key(&"a", "c").unwrap());
+        assert_eq!(map.get(&"c"), Some(&1));
+        assert_eq!(map.get(&"a"), None);
+        assert_eq!(map.get_index_of(&"c"), Some(0));
+
+        let err = map.replace_key(&"c", "b").unwrap_err();
+        assert_eq!((err.key, err.index), ("b", 1));
+        assert_eq!(map.get(&"c"), Some(&1));
+
+        assert!(!map.replace_key(&"missing", "d").unwrap());
+        assert!(map.replace_key(&"b", "b").unwrap());
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_flatmap_shrink_policy() {
+        use flatmap::ShrinkPolicy;
+
+        let mut map = FlatMap::new();
+        for i in 0..100 {
+            map.insert(i, i);
+        }
+        let full = map.capacity();
+
+        // deleting without a policy keeps the allocation
+        map.delete(&99);
+        assert_eq!(map.capacity(), full);
+
+        map.set_shrink_policy(Some(ShrinkPolicy::default()));
+        for i in 20..99 {
+            map.delete(&i);
+        }
+        assert!(map.capacity() < full);
+        assert!(map.capacity() >= 20);
+        assert_eq!(map.get(&5), Some(&5));
+
+        // never below min_capacity
+        for i in 0..20 {
+            map.delete(&i);
+        }
+        assert!(map.capacity() >= 16);
+        assert_eq!(map.shrink_policy(), Some(ShrinkPolicy::new(4, 16)));
+    }
+
+    #[test]
+    #[should_panic(expected = "divisor must be at least 2")]
+    fn test_shrink_policy_rejects_small_divisor() {
+        flatmap::ShrinkPolicy::new(1, 0);
+    }
+
+    #[test]
+    fn test_shrink_policy_try_new() {
+        use flatmap::ShrinkPolicy;
+
+        assert_eq!(ShrinkPolicy::try_new(0, 8), None);
+        assert_eq!(ShrinkPolicy::try_new(1, 8), None);
+        let policy = ShrinkPolicy::try_new(2, 8).unwrap();
+        assert_eq!((policy.divisor(), policy.min_capacity()), (2, 8));
+    }
+
+    #[test]
+    fn test_flatmap_growth_strategy() {
+        use flatmap::GrowthStrategy;
+
+        let mut exact = FlatMap::with_capacity_and_growth(2, GrowthStrategy::Exact);
+        for i in 0..5 {
+            exact.insert(i, ());
+            assert_eq!(exact.capacity(), (i + 1).max(2));
+        }
+
+        let mut custom = FlatMap::with_capacity_and_growth(0, GrowthStrategy::Custom(|len| len + 10));
+        custom.insert(0, ());
+        assert_eq!(custom.capacity(), 10);
+        for i in 1..11 {
+            custom.insert(i, ());
+        }
+        assert_eq!(custom.capacity(), 20);
+        assert!(matches!(custom.growth(), GrowthStrategy::Custom(_)));
+
+        let mut set = FlatSet::with_capacity_and_growth(0, GrowthStrategy::Exact);
+        set.insert(1);
+        set.insert(2);
+        assert_eq!(set.capacity(), 2);
+    }
+
+    #[test]
+    fn test_flatmap_clone_from_reuses_allocation() {
+        let mut source = FlatMap::new();
+        for i in 0..8 {
+            source.insert(i, i.to_string());
+        }
+        let mut snapshot = source.clone();
+        let ptr = snapshot.as_slice().as_ptr();
+
+        source.insert(3, "three".to_string());
+        source.delete(&7);
+        snapshot.clone_from(&source);
+        assert_eq!(snapshot.as_slice().as_ptr(), ptr);
+        assert_eq!(snapshot.len(), 7);
+        assert_eq!(snapshot.get(&3).map(String::as_str), Some("three"));
+
+        let source = FlatSet::from_iter(0..4);
+        let mut snapshot = FlatSet::with_capacity(16);
+        let ptr = snapshot.as_slice().as_ptr();
+        snapshot.clone_from(&source);
+        assert_eq!(snapshot.as_slice().as_ptr(), ptr);
+        assert!(snapshot.has(&3));
+    }
+
+    #[test]
+    fn test_flatmap_try_retain() {
+        let mut map = FlatMap::from_entries((0..6).map(|i| FlatMapEntry::new(i, i)));
+        assert_eq!(map.try_retain(|_, v| Ok::<_, ()>(*v % 2 == 0)), Ok(()));
+        assert_eq!(map.iter().map(|e| *e.key()).collect::<Vec<_>>(), vec![0, 2, 4]);
+
+        let err = map.try_retain(|k, _| if *k == 2 { Err("bad key") } else { Ok(false) });
+        assert_eq!(err, Err("bad key"));
+        // 0 was visited and dropped, 2 and everything after is untouched
+        assert_eq!(map.iter().map(|e| *e.key()).collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_flatmap_try_merge() {
+        let mut config = FlatMap::new();
+        config.insert("port", 80);
+        config.insert("workers", 4);
+
+        let mut overrides = FlatMap::new();
+        overrides.insert("workers", 4);
+        overrides.insert("timeout", 30);
+        let merged = config.try_merge(overrides, |_, a, b| if *a == b { Ok(b) } else { Err(()) });
+        assert_eq!(merged, Ok(()));
+        assert_eq!(config.get(&"timeout"), Some(&30));
+
+        let mut conflicting = FlatMap::new();
+        conflicting.insert("port", 8080);
+        let err = config.try_merge(conflicting, |k, _, _| Err(format!("conflicting {k}")));
+        assert_eq!(err.unwrap_err(), "conflicting port");
+        assert_eq!(config.get(&"port"), Some(&80));
+    }
+
+    #[test]
+    fn test_flatmap_apply_batch_rolls_back() {
+        use flatmap::{BatchError, BatchOp};
+
+        let mut config = FlatMap::from_entries([("a", 1), ("b", 2), ("c", 3)].into_iter().map(FlatMapEntry::from));
+        let pairs = |map: &FlatMap<&'static str, i32>| map.iter().map(|e| (*e.key(), *e.value())).collect::<Vec<_>>();
+        let before = pairs(&config);
+
+        let failed = config.apply_batch([
+            BatchOp::Delete("a"),
+            BatchOp::Insert("b", 20),
+            BatchOp::InsertNew("d", 4),
+            BatchOp::DeleteExisting("c"),
+            BatchOp::InsertNew("b", 0),
+        ]);
+        assert_eq!(failed, Err((BatchError::KeyExists, 4)));
+        assert_eq!(pairs(&config), before);
+
+        assert_eq!(config.apply_batch([BatchOp::DeleteExisting("z")]), Err((BatchError::KeyMissing, 0)));
+
+        config
+            .apply_batch([BatchOp::Delete("a"), BatchOp::Insert("b", 20), BatchOp::InsertNew("d", 4)])
+            .unwrap();
+        assert_eq!(config.get(&"a"), None);
+        assert_eq!(config.get(&"b"), Some(&20));
+        assert_eq!(config.get(&"d"), Some(&4));
+    }
+
+    #[test]
+    fn test_flatmap_scoped_rollback() {
+        let mut vars: FlatMap<&str, i32> = [("x", 1), ("y", 2), ("z", 3)].into_iter().into();
+        let pairs = |map: &FlatMap<&'static str, i32>| map.iter().map(|e| (*e.key(), *e.value())).collect::<Vec<_>>();
+        let before = pairs(&vars);
+
+        {
+            let mut outer = vars.scoped();
+            assert!(outer.insert("x", 10));
+            assert!(outer.delete(&"y"));
+            assert!(!outer.insert("w", 4));
+            {
+                let mut inner = outer.scoped();
+                inner.insert("x", 100);
+                inner.delete(&"z");
+                assert_eq!(inner.get(&"x"), Some(&100));
+            }
+            assert_eq!(outer.get(&"x"), Some(&10));
+            assert!(outer.contains_key(&"z"));
+
+            let mut inner = outer.scoped();
+            inner.insert("v", 5);
+            inner.commit();
+            assert_eq!(outer.get(&"v"), Some(&5));
+        }
+        assert_eq!(pairs(&vars), before);
+
+        let mut scope = vars.scoped();
+        scope.delete(&"x");
+        scope.insert("y", 20);
+        scope.commit();
+        assert_eq!(vars.get(&"x"), None);
+        assert_eq!(vars.get(&"y"), Some(&20));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "stale EntryIndex 2")]
+    fn test_flatmap_scoped_delete_invalidates_entry_index() {
+        let mut vars: FlatMap<&str, i32> = [("x", 1), ("y", 2), ("z", 3)].into_iter().into();
+        let mut scope = vars.scoped();
+        let index = scope.as_map().entry_index(&"z").unwrap();
+        scope.delete(&"x");
+        scope.as_map().get_at(index);
+    }
+
+    #[test]
+    fn test_flatmap_allocated_bytes() {
+        let entry = std::mem::size_of::<FlatMapEntry<u64, u32>>();
+        let mut map: FlatMap<u64, u32> = FlatMap::with_capacity(10);
+        map.insert(1, 1);
+        assert_eq!(map.allocated_bytes(), map.capacity() * entry);
+        assert!(map.allocated_bytes() >= 10 * entry);
+
+        map.shrink_to_fit();
+        assert_eq!(map.allocated_bytes(), entry);
+        assert_eq!(map.freeze().allocated_bytes(), entry);
+
+        let set = FlatSet::from_storage([1u16, 2, 3]).unwrap();
+        assert_eq!(set.allocated_bytes(), 6);
+    }
+
+    #[test]
+    fn test_flatmap_entry_index() {
+        let mut map = FlatMap::from_entries((0..4).map(|i| FlatMapEntry::new(i, i * 10)));
+        let index = map.entry_index(&3).unwrap();
+        assert_eq!(index.get(), 3);
+        map.insert(4, 40);
+        // appending doesn't move entries
+        assert_eq!(map.get_at(index).map(|e| *e.value()), Some(30));
+        *map.get_at_mut(index).unwrap().1 = 31;
+        assert_eq!(map.get(&3), Some(&31));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "stale EntryIndex 3")]
+    fn test_flatmap_stale_entry_index_panics() {
+        let mut map = FlatMap::from_entries((0..4).map(|i| FlatMapEntry::new(i, i)));
+        let index = map.entry_index(&3).unwrap();
+        map.delete(&0);
+        map.get_at(index);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "stale EntryIndex 1")]
+    fn test_flatmap_clone_from_invalidates_entry_index() {
+        let mut map = FlatMap::from_entries((0..4).map(|i| FlatMapEntry::new(i, i)));
+        let index = map.entry_index(&1).unwrap();
+        map.clone_from(&FlatMap::from_entries((5..9).map(|i| FlatMapEntry::new(i, i))));
+        map.get_at(index);
+    }
+
+    #[test]
+    fn test_flatmap_chunked_lookup() {
+        // small Copy keys take the chunked scan, hit every position within and after the chunks
+        for len in 0..11u32 {
+            let map = FlatMap::from_entries((0..len).map(|i| FlatMapEntry::new(i, i * 2)));
+            for i in 0..len {
+                assert_eq!(map.get(&i), Some(&(i * 2)));
+                assert_eq!(map.get_index_of(&i), Some(i as usize));
+            }
+            assert_eq!(map.get(&len), None);
+        }
+    }
+
+    #[test]
+    fn test_flatmap_interned_lookup() {
+        use std::sync::Arc;
+
+        let interned: Vec<Arc<str>> = ["alpha", "beta"].into_iter().map(Arc::from).collect();
+        let mut map = FlatMap::new();
+        for (i, key) in interned.iter().enumerate() {
+            map.insert(Arc::clone(key), i);
+        }
+
+        assert_eq!(map.get_interned(&interned[1]), Some(&1));
+        // a different allocation still matches by contents
+        assert_eq!(map.get_index_of_interned(&Arc::from("alpha")), Some(0));
+        assert!(!map.contains_key_interned(&Arc::from("gamma")));
+
+        let set = FlatSet::from_iter(["x", "y"].into_iter());
+        assert!(set.has_interned(&"y"));
+        assert!(!set.has_interned(&"z"));
+    }
+
     #[test]
     fn test_flatmap_iteration() {
         let mut map = FlatMap::new();
@@ -76,6 +623,108 @@ mod flatmap_tests {
         assert_eq!(map.get(&"b"), Some(&2));
     }
 
+    #[test]
+    fn test_flatmap_iter_sorted() {
+        let mut map = FlatMap::new();
+        map.insert("c", 3);
+        map.insert("a", 1);
+        map.insert("b", 2);
+
+        let sorted: Vec<_> = map.iter_sorted().map(|entry| *entry.value()).collect();
+        assert_eq!(sorted, vec![1, 2, 3]);
+
+        let keys: Vec<_> = map.keys_sorted().copied().collect();
+        assert_eq!(keys, vec!["a", "b", "c"]);
+
+        // layout is untouched
+        assert_eq!(map.as_slice()[0].key(), &"c");
+    }
+
+    #[test]
+    fn test_flatmap_min_max() {
+        let mut map = FlatMap::new();
+        assert!(map.min_key().is_none());
+
+        map.insert("m", 7);
+        map.insert("z", 1);
+        map.insert("a", 4);
+
+        assert_eq!(map.min_key().unwrap().key(), &"a");
+        assert_eq!(map.max_key().unwrap().key(), &"z");
+        assert_eq!(map.min_by_key(|entry| *entry.value()).unwrap().key(), &"z");
+        assert_eq!(map.max_by_key(|entry| *entry.value()).unwrap().key(), &"m");
+    }
+
+    #[test]
+    fn test_flatmap_partition() {
+        let map = FlatMap::from([("a", 1), ("b", 2), ("c", 3), ("d", 4)].into_iter());
+        let (even, odd) = map.partition(|_, v| v % 2 == 0);
+
+        assert_eq!(even.keys_sorted().copied().collect::<Vec<_>>(), vec!["b", "d"]);
+        assert_eq!(odd.keys_sorted().copied().collect::<Vec<_>>(), vec!["a", "c"]);
+        even.assert_invariants();
+    }
+
+    #[test]
+    fn test_flatmap_invert() {
+        let mut ids = FlatMap::new();
+        ids.insert(1, "one");
+        ids.insert(2, "two");
+
+        let names = ids.clone().invert().unwrap();
+        assert_eq!(names.get(&"one"), Some(&1));
+        assert_eq!(names.get(&"two"), Some(&2));
+
+        ids.insert(3, "one");
+        assert_eq!(ids.invert().unwrap_err(), DuplicateKeyError::new(0, 2));
+    }
+
+    #[test]
+    fn test_flatmap_value_lookup() {
+        let mut map = FlatMap::new();
+        map.insert("a", 1);
+        map.insert("b", 2);
+        map.insert("c", 1);
+
+        let mut keys: Vec<_> = map.keys_for_value(&1).copied().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "c"]);
+        assert_eq!(map.keys_for_value(&3).count(), 0);
+        assert!(map.contains_value(&2));
+        assert!(!map.contains_value(&3));
+    }
+
+    #[test]
+    fn test_flatmap_prefix_search() {
+        let mut commands = FlatMap::new();
+        commands.insert("commit", 1);
+        commands.insert("config", 2);
+        commands.insert("checkout", 3);
+
+        let mut keys: Vec<_> = commands.keys_with_prefix("co").copied().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["commit", "config"]);
+        assert_eq!(commands.iter_prefix("check").count(), 1);
+        assert_eq!(commands.iter_prefix("x").count(), 0);
+        assert_eq!(commands.iter_prefix("").count(), 3);
+    }
+
+    #[test]
+    fn test_flatmap_longest_prefix_match() {
+        let mut routes = FlatMap::new();
+        routes.insert("/", "root");
+        routes.insert("/api", "api");
+        routes.insert("/api/users", "users");
+
+        assert_eq!(routes.longest_prefix_match("/api/users/7"), Some((&"/api/users", &"users")));
+        assert_eq!(routes.longest_prefix_match("/api/items"), Some((&"/api", &"api")));
+        assert_eq!(routes.longest_prefix_match("/static"), Some((&"/", &"root")));
+        assert_eq!(routes.longest_prefix_match("static"), None);
+
+        let mime = ConstantFlatMap::from([("text/", 1), ("text/html", 2)]);
+        assert_eq!(mime.longest_prefix_match("text/html; charset=utf-8"), Some((&"text/html", &2)));
+    }
+
     #[test]
     fn test_flatmap_into_iterator() {
         let mut map = FlatMap::new();
@@ -83,11 +732,39 @@ mod flatmap_tests {
         map.insert("y", 200);
         
         let mut collected: Vec<_> = map.into_iter().collect();
-        collected.sort_by_key(|entry| *entry.key());
-        
-        assert_eq!(collected.len(), 2);
-        assert_eq!(collected[0].key(), &"x");
-        assert_eq!(collected[0].value(), &100);
+        collected.sort();
+
+        assert_eq!(collected, vec![("x", 100), ("y", 200)]);
+
+        let mut map = FlatMap::new();
+        map.insert("z", 1);
+        let mut seen = Vec::new();
+        for (k, v) in map {
+            seen.push((k, v));
+        }
+        assert_eq!(seen, vec![("z", 1)]);
+    }
+
+    #[test]
+    fn test_flatmap_raw_storage() {
+        let mut map = FlatMap::new();
+        map.insert(3, "c");
+        map.insert(1, "a");
+        map.insert(2, "b");
+
+        assert_eq!(map.as_slice().len(), 3);
+
+        unsafe { map.as_mut_slice() }.sort_by_key(|entry| *entry.key());
+        let position = map
+            .as_slice()
+            .binary_search_by_key(&2, |entry| *entry.key());
+        assert_eq!(position, Ok(1));
+
+        let inner = map.into_inner();
+        assert_eq!(inner[0].key(), &1);
+
+        let map = unsafe { FlatMap::from_vec_unchecked(inner) };
+        assert_eq!(map.get(&3), Some(&"c"));
     }
 
     #[test]
@@ -108,6 +785,8 @@ mod flatmap_tests {
         let map = ConstantFlatMap::from([("a", 1), ("b", 2)]);
         assert_eq!(map.get(&"a"), Some(&1));
         assert_eq!(map.get(&"b"), Some(&2));
+        assert!(map.contains_key(&"a"));
+        assert!(!map.contains_key(&"c"));
     }
 
     #[test]
@@ -120,8 +799,32 @@ mod flatmap_tests {
         let result = ConstantFlatMap::from_entries(entries);
         assert!(result.is_err());
         if let Err(err) = result {
-            assert_eq!(err, (0, 1));
+            assert_eq!(err, DuplicateKeyError::new(0, 1));
+        }
+    }
+
+    #[test]
+    fn test_duplicate_key_error_propagates() {
+        fn build() -> Result<FlatMap<&'static str, i32>, Box<dyn std::error::Error>> {
+            let map = FlatMap::from_storage(vec![FlatMapEntry::new("a", 1), FlatMapEntry::new("a", 2)])?;
+            Ok(map)
         }
+
+        let err = build().unwrap_err();
+        assert_eq!(err.to_string(), "duplicate keys at indices 0 and 1");
+        assert_eq!(<(usize, usize)>::from(DuplicateKeyError::new(0, 1)), (0, 1));
+    }
+
+    #[test]
+    fn test_constant_flatmap_from_entries_ord() {
+        let entries = [(5, 'a'), (3, 'b'), (9, 'c'), (3, 'd'), (5, 'e')].map(FlatMapEntry::from);
+        let quadratic = ConstantFlatMap::from_entries(entries.clone()).unwrap_err();
+        assert_eq!(quadratic, DuplicateKeyError::new(0, 4));
+        assert_eq!(ConstantFlatMap::from_entries_ord(entries).unwrap_err(), quadratic);
+
+        let keys: [FlatMapEntry<u32, u32>; 1000] =
+            std::array::from_fn(|i| FlatMapEntry::new((i as u32 * 7919) % 1000, 0));
+        assert!(ConstantFlatMap::from_entries_ord(keys).is_ok());
     }
 
     #[test]
@@ -135,11 +838,76 @@ mod flatmap_tests {
         let count_mut = map.iter_mut().count();
         assert_eq!(count_mut, 2);
     }
-}
 
-#[cfg(test)]
-mod flatset_tests {
-    use super::*;
+    #[test]
+    fn test_constant_flatmap_concat() {
+        let base = ConstantFlatMap::from([("a", 1), ("b", 2)]);
+        let extension = ConstantFlatMap::from([("c", 3)]);
+
+        let merged: ConstantFlatMap<_, _, 3> = base.clone().concat(extension).unwrap();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.get(&"a"), Some(&1));
+        assert_eq!(merged.get(&"c"), Some(&3));
+
+        let clashing = ConstantFlatMap::from([("b", 20)]);
+        let result: Result<ConstantFlatMap<_, _, 3>, _> = base.concat(clashing);
+        assert_eq!(result.unwrap_err(), DuplicateKeyError::new(1, 2));
+    }
+
+    #[test]
+    fn test_constant_flatmap_concat_const() {
+        const BASE: ConstantFlatMap<&str, u8, 2> =
+            unsafe { ConstantFlatMap::from_entries_unchecked([FlatMapEntry::new("if", 0), FlatMapEntry::new("else", 1)]) };
+        const DIALECT: ConstantFlatMap<&str, u8, 1> =
+            unsafe { ConstantFlatMap::from_entries_unchecked([FlatMapEntry::new("elif", 2)]) };
+        const KEYWORDS: ConstantFlatMap<&str, u8, 3> = match BASE.concat_const(&DIALECT) {
+            Ok(keywords) => keywords,
+            Err(_) => panic!("duplicate keyword"),
+        };
+        assert_eq!(KEYWORDS.get(&"elif"), Some(&2));
+
+        const CLASH: Result<ConstantFlatMap<&str, u8, 4>, DuplicateKeyError> = BASE.concat_const(&BASE);
+        assert_eq!(CLASH.unwrap_err(), DuplicateKeyError::new(0, 2));
+
+        let ports: ConstantFlatMap<u16, u8, 2> = unsafe { ConstantFlatMap::from_entries_unchecked([FlatMapEntry::new(80, 0), FlatMapEntry::new(0x50, 1)]) };
+        assert_eq!(ports.concat_const::<0, 2>(&ConstantFlatMap::from([] as [(u16, u8); 0])).unwrap_err(), DuplicateKeyError::new(0, 1));
+    }
+
+    #[test]
+    fn test_constant_flatmap_len_and_into_iter() {
+        let map = ConstantFlatMap::from([("x", 1), ("y", 2)]);
+        assert_eq!(map.len(), 2);
+        assert!(!map.is_empty());
+
+        let values: Vec<_> = map.clone().into_iter().map(|(_, v)| v).collect();
+        assert_eq!(values, vec![1, 2]);
+
+        let inner = map.into_inner();
+        assert_eq!(inner[1].key(), &"y");
+    }
+}
+
+#[cfg(test)]
+mod flatset_tests {
+    use super::*;
+
+    #[test]
+    fn test_flatset_shrink_policy() {
+        let mut set = FlatSet::from_iter(0..64);
+        set.set_shrink_policy(Some(flatmap::ShrinkPolicy::new(2, 4)));
+        set.remove_many(&(0..60).collect::<Vec<_>>());
+        assert!(set.capacity() < 64);
+        assert!(set.has(&63));
+    }
+
+    #[test]
+    fn test_flatset_replace_key() {
+        let mut set = FlatSet::from_iter(vec![1, 2, 3].into_iter());
+        assert!(set.replace_key(&2, 5).unwrap());
+        assert!(set.has(&5) && !set.has(&2));
+        assert_eq!(set.replace_key(&5, 3).unwrap_err().index, 2);
+        assert!(!set.replace_key(&9, 10).unwrap());
+    }
 
     #[test]
     fn test_flatset_basic_operations() {
@@ -155,6 +923,85 @@ mod flatset_tests {
         assert!(!set.delete(&"nonexistent"));
     }
 
+    #[test]
+    fn test_flatset_has_many() {
+        let set = FlatSet::from_iter([1, 2, 3].into_iter());
+        assert_eq!(set.has_many(&[&3, &4, &1]), vec![true, false, true]);
+    }
+
+    #[test]
+    fn test_flatset_remove_many() {
+        let mut set = FlatSet::from_iter(0..6);
+        assert_eq!(set.remove_many(&[0, 5, 2, 9]), 3);
+        let mut items: Vec<_> = set.into_iter().collect();
+        items.sort();
+        assert_eq!(items, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn test_flatset_pop() {
+        let mut set = FlatSet::from_iter([1, 2].into_iter());
+        let mut popped = vec![set.pop().unwrap(), set.pop().unwrap()];
+        popped.sort();
+        assert_eq!(popped, vec![1, 2]);
+        assert_eq!(set.pop(), None);
+    }
+
+    #[test]
+    fn test_flatset_extend() {
+        let mut set = FlatSet::new();
+        set.insert(1);
+        set.extend([1, 2, 3, 2]);
+        assert_eq!(set.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_flatset_partition() {
+        let (small, large) = FlatSet::from([1, 20, 3, 40].into_iter()).partition(|&n| n < 10);
+        assert_eq!(small.as_slice(), &[1, 3]);
+        assert_eq!(large.as_slice(), &[20, 40]);
+    }
+
+    #[test]
+    fn test_flatset_in_place_algebra() {
+        let b = FlatSet::from_iter([2, 3, 4].into_iter());
+
+        let mut union = FlatSet::from_iter([1, 2].into_iter());
+        union.union_with(&b);
+        let mut items: Vec<_> = union.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, vec![1, 2, 3, 4]);
+
+        let mut intersection = FlatSet::from_iter([1, 2, 3].into_iter());
+        intersection.intersect_with(&b);
+        let mut items: Vec<_> = intersection.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, vec![2, 3]);
+
+        let mut difference = FlatSet::from_iter([1, 2, 3].into_iter());
+        difference.difference_with(&b);
+        assert_eq!(difference.iter().copied().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_flatset_raw_storage() {
+        let mut set = FlatSet::from_iter([3, 1, 2].into_iter());
+        unsafe { set.as_mut_slice() }.sort();
+        assert_eq!(set.as_slice(), &[1, 2, 3]);
+        assert_eq!(set.as_slice().binary_search(&2), Ok(1));
+
+        let items = set.into_vec();
+        let set = unsafe { FlatSet::from_vec_unchecked(items) };
+        assert!(set.has(&3));
+    }
+
+    #[test]
+    fn test_flatset_prefix_search() {
+        let set = FlatSet::from_iter(["src/lib.rs", "src/map.rs", "tests/a.rs"].into_iter().map(String::from));
+        assert_eq!(set.iter_prefix("src/").count(), 2);
+        assert_eq!(set.iter_prefix("tests/").next().unwrap(), "tests/a.rs");
+    }
+
     #[test]
     fn test_flatset_from_iter() {
         let items = vec!["a", "b", "a"]; // duplicate
@@ -167,6 +1014,16 @@ mod flatset_tests {
         assert_eq!(count, 2); // duplicates removed
     }
 
+    #[test]
+    fn test_flatset_from_sorted() {
+        let set = FlatSet::from_sorted_iter(["a", "b", "c"].into_iter()).unwrap();
+        assert!(set.has(&"b"));
+        assert_eq!(FlatSet::from_sorted_iter([3, 1].into_iter()).unwrap_err(), 1);
+
+        let set = unsafe { FlatSet::from_sorted_vec_unchecked(vec![1, 5, 9]) };
+        assert_eq!(set.iter().count(), 3);
+    }
+
     #[test]
     fn test_flatset_from_iterator_trait() {
         let items = vec![1, 2, 3];
@@ -178,6 +1035,12 @@ mod flatset_tests {
         assert!(!set.has(&4));
     }
 
+    #[test]
+    fn test_flatset_from_array_iter() {
+        let set: FlatSet<_> = [1, 2, 1].into_iter().collect();
+        assert_eq!(set.iter().count(), 2);
+    }
+
     #[test]
     fn test_flatset_iteration() {
         let mut set = FlatSet::new();
@@ -209,10 +1072,16 @@ mod flatset_tests {
         let result = ConstantFlatSet::from_entries([1, 2, 1]); // duplicate
         assert!(result.is_err());
         if let Err(err) = result {
-            assert_eq!(err, (0, 2));
+            assert_eq!(err, DuplicateKeyError::new(0, 2));
         }
     }
 
+    #[test]
+    fn test_constant_flatset_from_entries_ord() {
+        assert_eq!(ConstantFlatSet::from_entries_ord([4, 1, 4, 1]).unwrap_err(), DuplicateKeyError::new(0, 2));
+        assert!(ConstantFlatSet::from_entries_ord([4, 1, 2]).is_ok());
+    }
+
     #[test]
     fn test_constant_flatset_iteration() {
         let set = unsafe { ConstantFlatSet::from_entries_unchecked(["x", "y", "z"]) };
@@ -224,6 +1093,1029 @@ mod flatset_tests {
         items.sort();
         assert_eq!(items, vec![&"x", &"y", &"z"]);
     }
+
+    #[test]
+    fn test_constant_flatset_len_and_into_iter() {
+        let set = ConstantFlatSet::from_entries([1, 2, 3]).unwrap();
+        assert_eq!(set.len(), 3);
+        assert!(!set.is_empty());
+        assert_eq!(set.clone().into_iter().sum::<i32>(), 6);
+        assert_eq!(set.into_inner(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_flatset_bulk_insert() {
+        let mut perms: FlatSet<String> = ["read".to_string(), "write".to_string()].into_iter().into();
+        let extra = ["write".to_string(), "admin".to_string(), "admin".to_string()];
+        assert_eq!(perms.extend_from_slice(&extra), 1);
+        assert_eq!(perms.as_slice(), ["read", "write", "admin"]);
+
+        let added = perms.insert_all(["audit", "read", "audit"].map(String::from));
+        assert_eq!(added, 1);
+        assert_eq!(perms.iter().count(), 4);
+    }
+}
+
+#[cfg(test)]
+mod hybrid_tests {
+    use super::*;
+
+    #[test]
+    fn test_hybrid_map_migrates_past_threshold() {
+        let mut map = HybridMap::with_threshold(4);
+        for i in 0..4 {
+            assert_eq!(map.insert(i, i * 10), None);
+        }
+        assert!(!map.is_hashed());
+
+        assert_eq!(map.insert(4, 40), None);
+        assert!(map.is_hashed());
+        assert_eq!(map.len(), 5);
+
+        for i in 0..5 {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+        assert_eq!(map.insert(2, 200), Some(20));
+        assert_eq!(map.delete(&0), Some(0));
+        assert_eq!(map.iter().count(), 4);
+    }
+
+    #[test]
+    fn test_hybrid_map_small() {
+        let mut map = HybridMap::new();
+        map.insert("a", 1);
+        assert_eq!(map.insert("a", 2), Some(1));
+        assert_eq!(map.get(&"a"), Some(&2));
+        assert!(!map.is_hashed());
+        assert_eq!(map.delete(&"a"), Some(2));
+        assert!(map.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use flatmap::{DuplicateKeyError, DuplicatePolicy, FlatMap, FlatMapBuilder};
+
+    fn builder() -> FlatMapBuilder<&'static str, i32> {
+        let mut builder = FlatMapBuilder::new();
+        builder.push("a", 1).push("b", 2).push("a", 3);
+        builder.extend([("c", 4), ("a", 5)]);
+        builder
+    }
+
+    #[test]
+    fn test_builder_policies() {
+        assert_eq!(builder().len(), 5);
+        assert_eq!(builder().build(DuplicatePolicy::Error).unwrap_err(), DuplicateKeyError::new(0, 2));
+
+        let first = builder().build(DuplicatePolicy::FirstWins).unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first.get(&"a"), Some(&1));
+
+        let last = builder().build(DuplicatePolicy::LastWins).unwrap();
+        assert_eq!(last.get(&"a"), Some(&5));
+        assert_eq!(last.get(&"c"), Some(&4));
+
+        let mut unique = FlatMapBuilder::new();
+        unique.push(1, 'x').push(2, 'y');
+        assert_eq!(unique.build(DuplicatePolicy::Error).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_builder_merge() {
+        let merged = builder().build_merge(|_, existing, value| *existing += value);
+        assert_eq!(merged.get(&"a"), Some(&9));
+        assert_eq!(merged.get(&"b"), Some(&2));
+    }
+
+    #[test]
+    fn test_builder_ord_and_hashed_match_eq() {
+        let pairs = |map: FlatMap<&'static str, i32>| map.iter().map(|e| (*e.key(), *e.value())).collect::<Vec<_>>();
+        for policy in [DuplicatePolicy::FirstWins, DuplicatePolicy::LastWins] {
+            let expected = pairs(builder().build(policy).unwrap());
+            assert_eq!(pairs(builder().build_ord(policy).unwrap()), expected);
+            assert_eq!(pairs(builder().build_hashed(policy).unwrap()), expected);
+        }
+        let merged = pairs(builder().build_merge(|_, existing, value| *existing += value));
+        assert_eq!(pairs(builder().build_merge_ord(|_, existing, value| *existing += value)), merged);
+        assert_eq!(pairs(builder().build_merge_hashed(|_, existing, value| *existing += value)), merged);
+
+        // the first pair find_duplicate would report, not the first duplicate pushed
+        let crossed = || {
+            let mut builder = FlatMapBuilder::new();
+            builder.push("a", 0).push("b", 1).push("b", 2).push("a", 3);
+            builder
+        };
+        let expected = DuplicateKeyError::new(0, 3);
+        assert_eq!(crossed().build(DuplicatePolicy::Error).unwrap_err(), expected);
+        assert_eq!(crossed().build_ord(DuplicatePolicy::Error).unwrap_err(), expected);
+        assert_eq!(crossed().build_hashed(DuplicatePolicy::Error).unwrap_err(), expected);
+    }
+}
+
+#[cfg(test)]
+mod byte_map_tests {
+    use flatmap::{FlatByteMap, FlatSet};
+
+    #[test]
+    fn test_flat_byte_map() {
+        let mut classes: FlatByteMap<&str> = (b'a'..=b'z').map(|b| (b, "lower")).collect();
+        classes.insert(b'_', "underscore");
+        assert_eq!(classes.len(), 27);
+
+        // exercises both the word-at-a-time scan and the remainder
+        for b in b'a'..=b'z' {
+            assert_eq!(classes.get(b), Some(&"lower"));
+        }
+        assert_eq!(classes.get(b'_'), Some(&"underscore"));
+        assert_eq!(classes.get(b'A'), None);
+        assert_eq!(classes.get(0x80), None);
+
+        assert_eq!(classes.insert(b'q', "letter"), Some("lower"));
+        assert_eq!(classes.delete(b'a'), Some("lower"));
+        assert!(!classes.contains_key(b'a'));
+        assert_eq!(classes.get(b'q'), Some(&"letter"));
+        assert_eq!(classes.iter().count(), 26);
+    }
+
+    #[test]
+    fn test_flatset_byte_membership() {
+        let delimiters = FlatSet::from_iter(b" \t\n,;()".iter().copied());
+        assert!(delimiters.has(&b';'));
+        assert!(!delimiters.has(&b'x'));
+    }
+}
+
+#[cfg(test)]
+mod compact_str_tests {
+    use flatmap::CompactStrFlatMap;
+
+    #[test]
+    fn test_compact_str_map() {
+        let mut map: CompactStrFlatMap<u32> = ["alpha", "beta", "gamma"].into_iter().zip(1..).collect();
+        assert_eq!(map.key_bytes(), 14);
+        assert_eq!(map.get("beta"), Some(&2));
+        assert_eq!(map.insert("beta", 20), Some(2));
+        assert_eq!(map.key_bytes(), 14);
+
+        *map.get_mut("gamma").unwrap() += 1;
+        assert_eq!(map.delete("alpha"), Some(1));
+        assert_eq!(map.delete("alpha"), None);
+        // 5 of 14 bytes unused isn't enough to compact
+        assert_eq!(map.key_bytes(), 14);
+
+        map.delete("gamma");
+        assert_eq!(map.key_bytes(), 4);
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![("beta", &20)]);
+        assert!(!map.contains_key("gamma"));
+    }
+}
+
+#[cfg(test)]
+mod enum_map_tests {
+    use flatmap::{EnumFlatMap, FlatMapKey, flatmap_key};
+
+    flatmap_key! {
+        #[derive(Debug)]
+        enum Direction {
+            North,
+            East,
+            South,
+            West,
+        }
+    }
+
+    #[test]
+    fn test_enum_flatmap() {
+        assert_eq!(Direction::COUNT, 4);
+
+        let mut degrees: EnumFlatMap<Direction, u16, { Direction::COUNT }> =
+            EnumFlatMap::from_fn(|direction| match direction {
+                Direction::North => 0,
+                Direction::East => 90,
+                Direction::South => 180,
+                Direction::West => 270,
+            });
+
+        assert_eq!(degrees.get(&Direction::South), &180);
+        *degrees.get_mut(&Direction::North) = 360;
+        assert_eq!(degrees.get(&Direction::North), &360);
+        assert_eq!(degrees.iter().count(), 4);
+        assert_eq!(degrees.into_inner().get(&Direction::West), Some(&270));
+    }
+}
+
+#[cfg(test)]
+mod indexed_tests {
+    use flatmap::IndexedFlatMap;
+
+    #[test]
+    fn test_indexed_map_rebuild_and_invalidate() {
+        let mut map = IndexedFlatMap::new();
+        for i in (0..100).rev() {
+            map.insert(i, i * 10);
+        }
+        map.rebuild_index();
+        assert!(map.is_indexed());
+        assert_eq!(map.get(&7), Some(&70));
+        assert_eq!(map.get(&100), None);
+
+        // overwriting a value keeps the index
+        assert_eq!(map.insert(7, 1), Some(70));
+        assert!(map.update(&8, |v| *v = 2));
+        assert!(map.is_indexed());
+        assert_eq!(map.get(&7), Some(&1));
+
+        map.delete(&0);
+        assert!(!map.is_indexed());
+        assert_eq!(map.get(&8), Some(&2));
+        assert_eq!(map.get(&0), None);
+    }
+}
+
+#[cfg(test)]
+mod map2_tests {
+    use flatmap::FlatMap2;
+
+    #[test]
+    fn test_flatmap2_partial_key() {
+        let mut slots: FlatMap2<u32, &str, i32> = [(1, "head", 10), (2, "head", 20), (1, "hand", 11)].into_iter().collect();
+        assert_eq!(slots.get(&1, &"hand"), Some(&11));
+        assert_eq!(slots.insert(1, "head", 12), Some(10));
+
+        let mut of_one: Vec<_> = slots.iter_k1(&1).map(|(slot, v)| (*slot, *v)).collect();
+        of_one.sort();
+        assert_eq!(of_one, vec![("hand", 11), ("head", 12)]);
+
+        assert_eq!(slots.delete(&2, &"head"), Some(20));
+        assert!(!slots.contains_k1(&2));
+        assert_eq!(slots.delete_k1(&1).len(), 2);
+        assert!(slots.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod multimap_tests {
+    use flatmap::{FlatHeaderMap, FlatMultiMap};
+
+    #[test]
+    fn test_flat_multimap() {
+        let mut map = FlatMultiMap::new();
+        map.append("tag", 1);
+        map.append("id", 2);
+        map.append("tag", 3);
+
+        assert_eq!(map.get(&"tag"), Some(&1));
+        assert_eq!(map.get_all(&"tag").copied().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(map.len(), 3);
+
+        assert_eq!(map.insert("tag", 4), vec![1, 3]);
+        assert_eq!(map.get_all(&"tag").copied().collect::<Vec<_>>(), vec![4]);
+        assert_eq!(map.delete(&"id"), vec![2]);
+        assert!(!map.contains_key(&"id"));
+    }
+
+    #[test]
+    fn test_flat_multimap_by_predicate() {
+        let mut map = FlatMultiMap::new();
+        for (k, v) in [(1, 'a'), (2, 'b'), (3, 'c'), (4, 'd'), (5, 'e')] {
+            map.append(k, v);
+        }
+
+        assert_eq!(map.get_by(|k| k % 2 == 0), Some(&'b'));
+        assert_eq!(map.get_all_by(|k| k % 2 == 1).collect::<Vec<_>>(), vec![&'a', &'c', &'e']);
+
+        assert_eq!(map.delete_by(|k| k % 2 == 1), vec!['a', 'c', 'e']);
+        let remaining: Vec<_> = map.iter().map(|e| *e.key()).collect();
+        assert_eq!(remaining, vec![2, 4]);
+        assert!(map.delete_by(|_| false).is_empty());
+    }
+
+    #[test]
+    fn test_flat_header_map() {
+        let mut headers = FlatHeaderMap::new();
+        headers.append("Set-Cookie", "a=1".to_string());
+        headers.append("set-cookie", "b=2".to_string());
+        headers.insert("Content-Type", "text/plain".to_string());
+
+        assert_eq!(headers.get("content-type").map(String::as_str), Some("text/plain"));
+        assert_eq!(headers.get_all("SET-COOKIE").count(), 2);
+        assert!(headers.contains("Set-Cookie"));
+
+        let replaced = headers.insert("CONTENT-TYPE", "text/html".to_string());
+        assert_eq!(replaced, vec!["text/plain".to_string()]);
+        assert_eq!(headers.len(), 3);
+
+        assert_eq!(headers.delete("set-cookie").len(), 2);
+        let names: Vec<_> = headers.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["CONTENT-TYPE"]);
+    }
+}
+
+#[cfg(test)]
+mod normalized_tests {
+    use flatmap::{NormalizedFlatMap, TrimLowercase};
+
+    #[test]
+    fn test_normalized_map_trim_lowercase() {
+        let mut env = NormalizedFlatMap::new(TrimLowercase);
+        env.insert(" Home ".to_string(), "/root");
+        assert_eq!(env.insert("HOME".to_string(), "/home"), Some("/root"));
+
+        assert_eq!(env.len(), 1);
+        assert_eq!(env.get(&"home".to_string()), Some(&"/home"));
+        assert_eq!(env.iter().next().unwrap().key(), "home");
+        assert_eq!(env.delete(&"  HoMe".to_string()), Some("/home"));
+        assert!(env.is_empty());
+    }
+
+    #[test]
+    fn test_normalized_map_closure() {
+        let mut map = NormalizedFlatMap::new(|k: &i32| k.abs());
+        map.insert(-3, "three");
+        assert!(map.contains_key(&3));
+        assert_eq!(map.get(&-3), Some(&"three"));
+    }
+}
+
+#[cfg(test)]
+mod observed_tests {
+    use flatmap::{MapChange, ObservedFlatMap};
+
+    #[test]
+    fn test_observed_flatmap() {
+        let mut log = Vec::new();
+        {
+            let mut map = ObservedFlatMap::new(|change: MapChange<'_, &str>| {
+                log.push(match change {
+                    MapChange::Inserted(k) => format!("+{k}"),
+                    MapChange::Overwritten(k) => format!("~{k}"),
+                    MapChange::Removed(k) => format!("-{k}"),
+                })
+            });
+
+            map.insert("a", 1);
+            map.insert("b", 2);
+            map.insert("a", 3);
+            assert!(map.update(&"b", |v| *v += 1));
+            assert!(!map.update(&"c", |v| *v += 1));
+            assert_eq!(map.delete(&"a"), Some(3));
+            assert_eq!(map.delete(&"a"), None);
+            assert_eq!(map.get(&"b"), Some(&3));
+        }
+
+        assert_eq!(log, vec!["+a", "+b", "~a", "~b", "-a"]);
+    }
+}
+
+#[cfg(test)]
+mod counter_tests {
+    use flatmap::FlatCounter;
+
+    #[test]
+    fn test_counter() {
+        let mut counter = FlatCounter::from_keys("abracadabra".chars());
+        assert_eq!(counter.get(&'a'), 5);
+        assert_eq!(counter.get(&'z'), 0);
+
+        assert_eq!(counter.increment(&'c'), 2);
+        assert_eq!(counter.add(&'z', 3), 3);
+        assert_eq!(counter.total(), 15);
+
+        let common: Vec<_> = counter
+            .most_common(3)
+            .into_iter()
+            .map(|e| (*e.key(), *e.value()))
+            .collect();
+        // equal counts keep insertion order
+        assert_eq!(common, vec![('a', 5), ('z', 3), ('b', 2)]);
+
+        let by_count: Vec<_> = counter.iter_by_count().map(|e| *e.key()).collect();
+        assert_eq!(by_count, vec!['a', 'z', 'b', 'r', 'c', 'd']);
+    }
+
+    #[test]
+    fn test_counter_saturates() {
+        let mut counter = FlatCounter::new();
+        assert_eq!(counter.add_owned("a", u64::MAX - 1), u64::MAX - 1);
+        assert_eq!(counter.add_owned("a", 5), u64::MAX);
+        assert_eq!(counter.add(&"a", 1), u64::MAX);
+        assert_eq!(counter.increment(&"a"), u64::MAX);
+
+        counter.add(&"b", u64::MAX);
+        assert_eq!(counter.total(), u64::MAX);
+    }
+}
+
+#[cfg(test)]
+mod priority_tests {
+    use flatmap::FlatPriorityMap;
+
+    #[test]
+    fn test_priority_map_pop_order() {
+        let mut tasks = FlatPriorityMap::new();
+        tasks.insert("eat", 3);
+        tasks.insert("sleep", 1);
+        tasks.insert("code", 5);
+
+        assert_eq!(tasks.peek_max().map(|e| *e.key()), Some("code"));
+        assert_eq!(tasks.peek_min().map(|e| *e.key()), Some("sleep"));
+
+        assert_eq!(tasks.update_priority(&"sleep", 9), Some(1));
+        assert_eq!(tasks.update_priority(&"missing", 9), None);
+        assert!(!tasks.contains_key(&"missing"));
+
+        assert_eq!(tasks.pop_max(), Some(("sleep", 9)));
+        assert_eq!(tasks.pop_min(), Some(("eat", 3)));
+        assert_eq!(tasks.pop_max(), Some(("code", 5)));
+        assert_eq!(tasks.pop_max(), None);
+        assert!(tasks.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod range_tests {
+    use flatmap::FlatRangeMap;
+
+    #[test]
+    fn test_range_map_lookup() {
+        let mut buckets = FlatRangeMap::new();
+        assert!(buckets.try_insert(10..20, "b").is_ok());
+        assert!(buckets.try_insert(0..10, "a").is_ok());
+        assert_eq!(buckets.try_insert(15..25, "c"), Err((15..25, 1)));
+
+        assert_eq!(buckets.get(&0), Some(&"a"));
+        assert_eq!(buckets.get(&10), Some(&"b"));
+        assert_eq!(buckets.get(&20), None);
+        assert_eq!(buckets.get_range_value(&12), Some((&(10..20), &"b")));
+
+        let starts: Vec<_> = buckets.iter().map(|(range, _)| range.start).collect();
+        assert_eq!(starts, vec![0, 10]);
+    }
+
+    #[test]
+    fn test_range_map_insert_splits() {
+        let mut map = FlatRangeMap::new();
+        map.insert(0..100, 'x');
+        map.insert(40..60, 'y');
+        map.insert(90..110, 'z');
+
+        let ranges: Vec<_> = map.iter().map(|(range, v)| (range.clone(), *v)).collect();
+        assert_eq!(
+            ranges,
+            vec![(0..40, 'x'), (40..60, 'y'), (60..90, 'x'), (90..110, 'z')]
+        );
+
+        map.insert(30..95, 'w');
+        let ranges: Vec<_> = map.iter().map(|(range, v)| (range.clone(), *v)).collect();
+        assert_eq!(ranges, vec![(0..30, 'x'), (30..95, 'w'), (95..110, 'z')]);
+
+        assert_eq!(map.delete(&50), Some((30..95, 'w')));
+        assert_eq!(map.get(&50), None);
+        assert_eq!(map.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod traits_tests {
+    use super::*;
+    use flatmap::{FlatMapRead, FlatMapWrite, FlatSetRead, FlatSetWrite};
+
+    fn total(map: &impl FlatMapRead<Key = &'static str, Value = i32>) -> i32 {
+        map.iter().map(|entry| *entry.value()).sum()
+    }
+
+    fn bump(map: &mut impl FlatMapWrite<Key = &'static str, Value = i32>, k: &'static str) {
+        let v = map.get(&k).copied().unwrap_or(0);
+        map.insert(k, v + 1);
+    }
+
+    #[test]
+    fn test_generic_over_map_variants() {
+        let table = ConstantFlatMap::from([("a", 1), ("b", 2)]);
+        let mut map = FlatMap::new();
+        bump(&mut map, "a");
+        bump(&mut map, "a");
+        let frozen = map.clone().freeze();
+
+        assert_eq!(total(&table), 3);
+        assert_eq!(total(&map), 2);
+        assert_eq!(total(&frozen), 2);
+        assert_eq!(total(&FlatMapView::from_slice(map.as_slice()).unwrap()), 2);
+        assert!(FlatMapRead::contains_key(&table, &"b"));
+        assert_eq!(FlatMapWrite::delete(&mut map, &"a"), Some(2));
+        assert!(FlatMapRead::is_empty(&map));
+    }
+
+    #[test]
+    fn test_generic_over_set_variants() {
+        fn count_hits(set: &impl FlatSetRead<Key = u8>, probes: &[u8]) -> usize {
+            probes.iter().filter(|p| set.has(p)).count()
+        }
+
+        let table = ConstantFlatSet::from_entries([1u8, 2, 3]).unwrap();
+        let mut set = FlatSet::new();
+        assert!(!FlatSetWrite::insert(&mut set, 2u8));
+        assert!(FlatSetWrite::insert(&mut set, 2u8));
+
+        assert_eq!(count_hits(&table, &[1, 2, 9]), 2);
+        assert_eq!(count_hits(&set, &[1, 2, 9]), 1);
+        assert_eq!(FlatSetRead::len(&set), 1);
+    }
+}
+
+#[cfg(test)]
+mod stable_tests {
+    use flatmap::StableFlatMap;
+
+    #[test]
+    fn test_stable_indices_survive_deletes() {
+        let mut map = StableFlatMap::new();
+        for k in ["a", "b", "c", "d"] {
+            map.insert(k, k.len());
+        }
+        let d = map.get_index_of(&"d").unwrap();
+
+        assert_eq!(map.delete(&"a"), Some(1));
+        assert_eq!(map.delete(&"a"), None);
+        assert_eq!(map.get_index_of(&"d"), Some(d));
+        assert_eq!(map.get_index(d).map(|e| *e.key()), Some("d"));
+        assert!(map.get_index(0).is_none());
+        assert_eq!((map.len(), map.tombstones()), (3, 1));
+
+        let (key, value) = map.get_index_mut(d).unwrap();
+        assert_eq!(*key, "d");
+        *value = 7;
+        assert_eq!(map.get_index(d).map(|e| *e.value()), Some(7));
+        assert!(map.get_index_mut(0).is_none());
+
+        // re-inserting appends rather than reusing the tombstone
+        assert_eq!(map.insert_full("a", 9), (4, None));
+
+        let mut moved = Vec::new();
+        map.compact_with(|old, new| moved.push((old, new)));
+        assert_eq!(moved, vec![(1, 0), (2, 1), (3, 2), (4, 3)]);
+        assert_eq!(map.tombstones(), 0);
+        assert_eq!(map.iter().map(|(i, e)| (i, *e.key())).collect::<Vec<_>>(),
+            vec![(0, "b"), (1, "c"), (2, "d"), (3, "a")]);
+
+        let flat = map.into_flat_map();
+        assert_eq!(flat.get(&"a"), Some(&9));
+    }
+}
+
+#[cfg(test)]
+mod storage_tests {
+    use super::*;
+    use flatmap::{InlineFlatMap, InlineFlatSet};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_inline_storage_map() {
+        let mut map: InlineFlatMap<u32, &str, 2> = FlatMap::default();
+        assert_eq!(map.try_insert(1, "a"), Ok(None));
+        assert_eq!(map.try_insert(2, "b"), Ok(None));
+        assert_eq!(map.try_insert(3, "c"), Err((3, "c")));
+        // overwriting doesn't need room
+        assert_eq!(map.try_insert(1, "z"), Ok(Some("a")));
+
+        assert_eq!(map.delete(&1), Some("z"));
+        assert_eq!(map.insert(3, "c"), None);
+        assert_eq!(map.get(&3), Some(&"c"));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "storage is full")]
+    fn test_inline_storage_insert_full_panics() {
+        let mut set: InlineFlatSet<u8, 1> = FlatSet::default();
+        set.insert(1);
+        set.insert(2);
+    }
+
+    #[test]
+    fn test_inline_storage_drops_items() {
+        let value = Rc::new(());
+        {
+            let mut set: InlineFlatSet<i32, 4> = FlatSet::default();
+            let mut map: InlineFlatMap<i32, Rc<()>, 4> = FlatMap::default();
+            for i in 0..3 {
+                map.insert(i, value.clone());
+                set.insert(i);
+            }
+            map.delete(&0);
+            assert_eq!(Rc::strong_count(&value), 3);
+            let copy = map.clone();
+            assert_eq!(Rc::strong_count(&value), 5);
+            drop(copy);
+            assert_eq!(set.try_insert(3), Ok(false));
+            assert_eq!(set.try_insert(4), Err(4));
+        }
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn test_buffer_backed_map_and_set() {
+        use flatmap::{BufferFlatMap, BufferFlatSet};
+        use std::mem::MaybeUninit;
+
+        let mut buf = [const { MaybeUninit::uninit() }; 3];
+        let mut map = BufferFlatMap::from_buffer(&mut buf);
+        assert_eq!(map.capacity(), 3);
+        for i in 0..3 {
+            assert_eq!(map.try_insert(i, i.to_string()), Ok(None));
+        }
+        assert_eq!(map.try_insert(3, "3".to_string()), Err((3, "3".to_string())));
+        assert_eq!(map.delete(&0).as_deref(), Some("0"));
+        assert_eq!(map.get(&2).map(String::as_str), Some("2"));
+        drop(map);
+
+        let mut buf = [MaybeUninit::uninit(); 2];
+        let mut set = BufferFlatSet::from_buffer(&mut buf);
+        assert_eq!(set.try_insert('a'), Ok(false));
+        assert_eq!(set.try_insert('a'), Ok(true));
+        assert_eq!(set.try_insert('b'), Ok(false));
+        assert_eq!(set.try_insert('c'), Err('c'));
+        assert_eq!(set.pop(), Some('b'));
+    }
+
+    #[test]
+    fn test_frozen_and_fixed_storage() {
+        let entries: Arc<[FlatMapEntry<&str, i32>]> =
+            vec![FlatMapEntry::new("a", 1), FlatMapEntry::new("b", 2)].into();
+        let map = FlatMap::from_storage(entries.clone()).unwrap();
+        assert_eq!(map.get(&"b"), Some(&2));
+        assert_eq!(map.into_storage().len(), 2);
+
+        let mut set = FlatSet::from_storage(vec![1, 2, 3].into_boxed_slice()).unwrap();
+        assert!(set.replace_key(&1, 4).unwrap());
+        assert!(set.has(&4));
+        assert_eq!(FlatSet::from_storage([1, 2, 1]).unwrap_err(), DuplicateKeyError::new(0, 2));
+    }
+}
+
+#[cfg(test)]
+mod view_tests {
+    use super::*;
+
+    #[test]
+    fn test_flatmap_view() {
+        let entries = [FlatMapEntry::new(1, "a"), FlatMapEntry::new(2, "b")];
+        let view = FlatMapView::from_slice(&entries).unwrap();
+
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.get(&2), Some(&"b"));
+        assert_eq!(view.get(&3), None);
+        assert_eq!(view.iter().count(), 2);
+
+        let duplicates = [FlatMapEntry::new(1, "a"), FlatMapEntry::new(1, "b")];
+        assert_eq!(FlatMapView::from_slice(&duplicates).unwrap_err(), DuplicateKeyError::new(0, 1));
+    }
+
+    #[test]
+    fn test_flatset_view() {
+        let items = [b'a', b'b', b'c'];
+        let view = unsafe { FlatSetView::from_slice_unchecked(&items) };
+
+        assert!(view.has(&b'b'));
+        assert!(!view.has(&b'z'));
+        assert_eq!(view.as_slice(), &items);
+        assert_eq!(FlatSetView::from_slice(&[1, 2, 1]).unwrap_err(), DuplicateKeyError::new(0, 2));
+    }
+
+    #[test]
+    fn test_views_from_bytes() {
+        let entries = [FlatMapEntry::new(1u32, 10u32), FlatMapEntry::new(2u32, 20u32)];
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                entries.as_ptr().cast::<u8>(),
+                std::mem::size_of_val(&entries),
+            )
+        };
+
+        let view = unsafe { FlatMapView::<u32, u32>::from_bytes_unchecked(bytes) }.unwrap();
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.get(&2), Some(&20));
+
+        let set = unsafe { FlatSetView::<u32>::from_bytes_unchecked(&bytes[..8]) }.unwrap();
+        assert!(set.has(&10));
+        assert!(unsafe { FlatSetView::<u32>::from_bytes_unchecked(&bytes[..7]) }.is_none());
+    }
+}
+
+#[cfg(test)]
+mod frozen_tests {
+    use super::*;
+
+    #[test]
+    fn test_frozen_flatmap() {
+        let mut map = FlatMap::with_capacity(16);
+        map.insert("a", 1);
+        map.insert("b", 2);
+
+        let frozen = map.freeze();
+        let shared = frozen.clone();
+        assert_eq!(frozen.len(), 2);
+        assert_eq!(frozen.get(&"a"), Some(&1));
+        assert_eq!(frozen.get(&"c"), None);
+        assert_eq!(frozen.as_slice().as_ptr(), shared.as_slice().as_ptr());
+
+        let handle = std::thread::spawn(move || *shared.get(&"b").unwrap());
+        assert_eq!(handle.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_frozen_flatmap_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<FrozenFlatMap<String, u32>>();
+    }
+}
+
+#[cfg(all(test, feature = "parallel"))]
+mod parallel_tests {
+    use super::*;
+
+    #[test]
+    fn test_par_from_entries_matches_sequential() {
+        let entries = || (0..50_000u32).map(|i| FlatMapEntry::new(i % 7919, i));
+        let par = FlatMap::par_from_entries(entries().collect());
+        let seq = FlatMap::from_entries_hashed(entries());
+
+        assert_eq!(par.len(), 7919);
+        let par: Vec<_> = par.iter().map(|e| (*e.key(), *e.value())).collect();
+        let seq: Vec<_> = seq.iter().map(|e| (*e.key(), *e.value())).collect();
+        assert_eq!(par, seq);
+    }
+
+    #[test]
+    fn test_par_extend_overwrites_in_place() {
+        let mut map = FlatMap::new();
+        map.insert(10_000u32, 0);
+        map.insert(1, 0);
+        map.par_extend((0..20_000u32).map(|i| FlatMapEntry::new(i, i)).collect());
+
+        assert_eq!(map.len(), 20_000);
+        assert_eq!(map.get_index_of(&10_000), Some(0));
+        assert_eq!(map.get(&10_000), Some(&10_000));
+        assert_eq!(map.get(&19_999), Some(&19_999));
+    }
+
+    #[test]
+    fn test_par_extend_matches_sequential_order() {
+        // the second half repeats every key in reverse, so last occurrences run backwards
+        let entries = |n: u32| (0..n).chain((0..n).rev()).enumerate().map(|(i, k)| FlatMapEntry::new(k, i));
+
+        for n in [5, 10_000] {
+            let mut par = FlatMap::new();
+            par.insert(7u32, usize::MAX);
+            par.par_extend(entries(n).collect());
+
+            let mut seq = FlatMap::new();
+            seq.insert(7u32, usize::MAX);
+            for entry in entries(n) {
+                let (k, v) = entry.into();
+                seq.insert(k, v);
+            }
+
+            let par: Vec<_> = par.iter().map(|e| (*e.key(), *e.value())).collect();
+            let seq: Vec<_> = seq.iter().map(|e| (*e.key(), *e.value())).collect();
+            assert_eq!(par, seq);
+            assert_eq!(par[1], (0, 2 * n as usize - 1));
+        }
+    }
+
+    #[test]
+    fn test_par_from_entries_keeps_last_occurrence_order() {
+        let entries = (0..10_000u32).chain((0..10_000).rev()).map(|k| FlatMapEntry::new(k, k));
+        let map = FlatMap::par_from_entries(entries.collect());
+        let keys: Vec<_> = map.iter().map(|e| *e.key()).collect();
+        assert_eq!(keys, (0..10_000).rev().collect::<Vec<_>>());
+    }
+}
+
+#[cfg(all(test, feature = "codegen"))]
+mod codegen_tests {
+    use super::*;
+    use flatmap::codegen::ConstantTable;
+
+    // written by ConstantTable::generate, the tests check they still match its output
+    mod generated {
+        include!("fixtures/countries.rs");
+        include!("fixtures/ids.rs");
+    }
+
+    #[test]
+    fn test_codegen_from_csv() {
+        let csv = "# code,name\nde,Germany\n\n\"fr\", \"France, \"\"Republic\"\"\"\n";
+        let mut table = ConstantTable::new("COUNTRIES", "&'static str", "&'static str");
+        table.read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(table.len(), 2);
+
+        let source = table.generate().unwrap();
+        assert!(source.starts_with(
+            "pub static COUNTRIES: ::flatmap::ConstantFlatMap<&'static str, &'static str, 2>"
+        ));
+        assert!(source.contains(r#"::flatmap::FlatMapEntry::new("de", "Germany"),"#));
+        assert!(source.contains(r#"::flatmap::FlatMapEntry::new("fr", "France, \"Republic\""),"#));
+        assert_eq!(source, include_str!("fixtures/countries.rs"));
+        assert_eq!(generated::COUNTRIES.get(&"fr"), Some(&"France, \"Republic\""));
+    }
+
+    #[test]
+    fn test_codegen_checks_expression_keys_on_first_use() {
+        // "1" and "0x1" differ as text but are the same key, so the table can't be trusted unchecked
+        let mut ids = ConstantTable::new("IDS", "u32", "char");
+        ids.entry("1", "'a'").entry("0x2", "'b'");
+        let source = ids.generate().unwrap();
+        assert!(!source.contains("unsafe"));
+        assert_eq!(source, include_str!("fixtures/ids.rs"));
+        assert_eq!(generated::IDS.get(&2), Some(&'b'));
+    }
+
+    #[test]
+    fn test_codegen_rejects_duplicates() {
+        let mut table = ConstantTable::new("IDS", "u32", "u8");
+        table.entry("1", "10").entry("2", "20").entry("1", "30");
+        assert_eq!(table.generate().unwrap_err(), DuplicateKeyError::new(0, 2));
+
+        let bad_row = "a,b,c\n";
+        assert!(table.read_csv(bad_row.as_bytes()).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "csv"))]
+mod csv_tests {
+    use flatmap::{DuplicatePolicy, FlatMap};
+
+    #[test]
+    fn test_flatmap_from_csv() {
+        let csv = "# key,value\nhost, localhost\nport,8080\n\nhost,\"example.com\"\n";
+
+        let map = FlatMap::from_csv(csv.as_bytes(), DuplicatePolicy::LastWins).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"host".to_string()).unwrap(), "example.com");
+
+        let first = FlatMap::from_csv(csv.as_bytes(), DuplicatePolicy::FirstWins).unwrap();
+        assert_eq!(first.get(&"host".to_string()).unwrap(), "localhost");
+
+        let err = FlatMap::from_csv(csv.as_bytes(), DuplicatePolicy::Error).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(FlatMap::from_csv("just one field".as_bytes(), DuplicatePolicy::LastWins).is_err());
+
+        let err = FlatMap::from_csv("a,1\nb,\"2\n".as_bytes(), DuplicatePolicy::LastWins).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "row 2 has an unterminated quote");
+        // a comma after an unclosed quote is part of the field, not a separator
+        let err = FlatMap::from_csv("\"a,1".as_bytes(), DuplicatePolicy::LastWins).unwrap_err();
+        assert_eq!(err.to_string(), "row 1 has an unterminated quote");
+    }
+
+    #[test]
+    fn test_flatmap_from_csv_keeps_quoted_whitespace() {
+        let csv = "indent, \"  \"\n\" padded key \" ,plain  \n";
+        let map = FlatMap::from_csv(csv.as_bytes(), DuplicatePolicy::Error).unwrap();
+        assert_eq!(map.get(&"indent".to_string()).unwrap(), "  ");
+        assert_eq!(map.get(&" padded key ".to_string()).unwrap(), "plain");
+    }
+}
+
+#[cfg(all(test, feature = "size-diagnostics"))]
+mod diagnostics_tests {
+    use super::*;
+    use flatmap::diagnostics;
+
+    #[test]
+    fn test_size_warning_threshold() {
+        assert_eq!(
+            diagnostics::size_warning_threshold(),
+            diagnostics::DEFAULT_SIZE_WARNING_THRESHOLD
+        );
+
+        // growing past the threshold only warns, it never changes behavior
+        let mut map = FlatMap::new();
+        for i in 0..=diagnostics::DEFAULT_SIZE_WARNING_THRESHOLD {
+            map.insert(i, i);
+        }
+        assert_eq!(map.len(), diagnostics::DEFAULT_SIZE_WARNING_THRESHOLD + 1);
+        assert!(diagnostics::size_warning_emitted());
+
+        // crossing the threshold again or building past it in bulk doesn't warn a second time
+        for _ in 0..3 {
+            map.delete(&0);
+            map.insert(0, 0);
+        }
+        let set = unsafe { FlatSet::from_vec_unchecked((0..1000).collect()) };
+        assert_eq!(set.iter().count(), 1000);
+        assert!(diagnostics::size_warning_emitted());
+    }
+}
+
+#[cfg(all(test, feature = "ffi"))]
+mod ffi_tests {
+    use super::*;
+    use flatmap::ffi::*;
+
+    #[test]
+    fn test_ffi_roundtrip() {
+        unsafe {
+            let map = flatmap_u64_new();
+            let mut out = 0;
+            assert!(!flatmap_u64_insert(map, 1, 10, &mut out));
+            assert!(flatmap_u64_insert(map, 1, 11, &mut out));
+            assert_eq!(out, 10);
+            assert!(!flatmap_u64_insert(map, 2, 20, std::ptr::null_mut()));
+
+            assert!(flatmap_u64_get(map, 2, &mut out));
+            assert_eq!(out, 20);
+            assert!(!flatmap_u64_get(map, 3, &mut out));
+            assert_eq!(flatmap_u64_len(map), 2);
+
+            let mut len = 0;
+            let entries = flatmap_u64_entries(map, &mut len);
+            let view = FlatMapView::from_raw_parts(entries, len);
+            assert_eq!(view.get(&1), Some(&11));
+
+            assert!(flatmap_u64_delete(map, 1, &mut out));
+            assert_eq!(out, 11);
+            flatmap_u64_free(map);
+        }
+    }
+}
+
+#[cfg(test)]
+mod encode_tests {
+    use super::*;
+    use flatmap::encode::DecodeError;
+
+    #[test]
+    fn test_flatmap_binary_roundtrip() {
+        let mut map = FlatMap::new();
+        map.insert("boot".to_string(), 0x8000_u32);
+        map.insert("kernel".to_string(), 0x10_0000_u32);
+
+        let mut bytes = Vec::new();
+        map.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes[0], 2);
+
+        let decoded = FlatMap::<String, u32>::read_from(&bytes).unwrap();
+        assert_eq!(decoded.get(&"kernel".to_string()), Some(&0x10_0000));
+        assert_eq!(decoded.len(), 2);
+
+        assert_eq!(
+            FlatMap::<String, u32>::read_from(&bytes[..bytes.len() - 1]).unwrap_err(),
+            DecodeError::UnexpectedEnd
+        );
+        bytes.push(0);
+        assert_eq!(
+            FlatMap::<String, u32>::read_from(&bytes).unwrap_err(),
+            DecodeError::TrailingBytes
+        );
+    }
+
+    #[test]
+    fn test_flatset_binary_roundtrip() {
+        let set = FlatSet::from_iter((0..200u16).map(|i| i * 3));
+        let mut bytes = Vec::new();
+        set.write_to(&mut bytes).unwrap();
+        // 200 needs a two byte length prefix
+        assert_eq!(bytes.len(), 2 + 200 * 2);
+
+        let decoded = FlatSet::<u16>::read_from(&bytes).unwrap();
+        assert!(decoded.has(&597));
+
+        let duplicated = [2, 7, 7];
+        assert_eq!(
+            FlatSet::<u8>::read_from(&duplicated).unwrap_err(),
+            DecodeError::Duplicate(DuplicateKeyError::new(0, 1))
+        );
+    }
+
+    #[test]
+    fn test_include_flatmap_blob() {
+        let map = flatmap::include_flatmap!(String, u32, "fixtures/regions.blob").unwrap();
+        assert_eq!(map.get(&"initrd".to_string()), Some(&0x20_0000));
+        assert_eq!(map.iter().next().unwrap().key(), "boot");
+
+        let mut bytes = Vec::new();
+        map.write_blob(&mut bytes).unwrap();
+        assert_eq!(bytes.as_slice(), include_bytes!("fixtures/regions.blob"));
+
+        assert_eq!(
+            FlatMap::<String, u32>::read_blob(&bytes[1..]).unwrap_err(),
+            DecodeError::InvalidData
+        );
+        let duplicated = [b'F', b'L', b'A', b'T', 1, 2, 5, 0, 5, 1];
+        assert_eq!(
+            FlatMap::<u8, u8>::read_blob(&duplicated).unwrap_err(),
+            DecodeError::Duplicate(DuplicateKeyError::new(0, 1))
+        );
+        let descending = [b'F', b'L', b'A', b'T', 1, 2, 7, 0, 5, 1];
+        assert_eq!(
+            FlatMap::<u8, u8>::read_blob(&descending).unwrap_err(),
+            DecodeError::InvalidData
+        );
+    }
 }
 
 #[cfg(test)]
@@ -272,4 +2164,78 @@ mod edge_cases {
         let set = unsafe { FlatSet::from_iter_unchecked(items.into_iter()) };
         assert!(set.has(&2));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_check_duplicates() {
+        let mut map = FlatMap::new();
+        map.insert(1, "a");
+        map.insert(2, "b");
+        assert_eq!(map.check_duplicates(), Ok(()));
+        map.assert_invariants();
+
+        let first = map.get_index(0).unwrap().clone();
+        let slice = unsafe { map.as_mut_slice() };
+        slice[1] = first;
+        assert_eq!(map.check_duplicates(), Err(DuplicateKeyError::new(0, 1)));
+
+        let set = unsafe { ConstantFlatSet::from_entries_unchecked([1, 2, 1]) };
+        assert_eq!(set.check_duplicates(), Err(DuplicateKeyError::new(0, 2)));
+    }
+
+    #[test]
+    fn test_duplicate_groups() {
+        let map = FlatMap::from([(1, "a"), (2, "b")].into_iter());
+        assert!(map.duplicate_groups().is_empty());
+
+        let mut map = FlatMap::from([(1, 'a'), (2, 'b'), (3, 'c'), (4, 'd'), (5, 'e')].into_iter());
+        let slice = unsafe { map.as_mut_slice() };
+        slice[2] = FlatMapEntry::new(1, 'c');
+        slice[3] = FlatMapEntry::new(2, 'd');
+        slice[4] = FlatMapEntry::new(1, 'e');
+        assert_eq!(map.duplicate_groups(), vec![vec![0, 2, 4], vec![1, 3]]);
+
+        let mut set = FlatSet::from([7, 8, 9].into_iter());
+        let slice = unsafe { set.as_mut_slice() };
+        slice[2] = 7;
+        assert_eq!(set.duplicate_groups(), vec![vec![0, 2]]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "duplicate keys at indices 0 and 2")]
+    fn test_unchecked_constructor_debug_assertion() {
+        let entries = vec![
+            FlatMapEntry::new("a", 1),
+            FlatMapEntry::new("b", 2),
+            FlatMapEntry::new("a", 3),
+        ];
+        let _ = unsafe { FlatMap::from_vec_unchecked(entries) };
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "FlatSet has duplicate items")]
+    fn test_unchecked_set_constructor_debug_assertion() {
+        let _ = unsafe { FlatSet::from_iter_unchecked([1, 1].into_iter()) };
+    }
+}
+
+#[cfg(all(test, feature = "urlencoded"))]
+mod urlencoded_tests {
+    use flatmap::FlatMultiMap;
+
+    #[test]
+    fn test_multimap_urlencoded_round_trip() {
+        let params = FlatMultiMap::from_urlencoded("?tag=a&tag=b%20c&q=caf%C3%A9+au+lait&flag&bad=%zz");
+        let tag = "tag".to_string();
+        let tags: Vec<_> = params.get_all(&tag).collect();
+        assert_eq!(tags, vec!["a", "b c"]);
+        assert_eq!(params.get(&"q".to_string()).unwrap(), "café au lait");
+        assert_eq!(params.get(&"flag".to_string()).unwrap(), "");
+        assert_eq!(params.get(&"bad".to_string()).unwrap(), "%zz");
+
+        let encoded = params.to_urlencoded();
+        assert_eq!(encoded, "tag=a&tag=b+c&q=caf%C3%A9+au+lait&flag=&bad=%25zz");
+        assert_eq!(FlatMultiMap::from_urlencoded(&encoded).to_urlencoded(), encoded);
+    }
+}